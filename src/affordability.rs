@@ -0,0 +1,294 @@
+// 购房能力评估：根据收入、现有负债和首付款，计算可承受的最高房价和贷款金额.
+// 首付款、房价与贷款金额以 unit 计，收入与月供以 unit 的基本单位计，例如单位为万元时收入以元计.
+
+use crate::currency;
+use crate::loan::{self, Loan, LoanType};
+
+// 限制房价的条件.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum Constraint{
+    // 月供与现有负债之和不超过收入的一定比例.
+    DebtToIncome,
+    // 首付款不低于房价的一定比例.
+    DownPayment,
+}
+
+impl Constraint{
+    pub fn to_str(&self) -> &str{
+        match self{
+            Constraint::DebtToIncome => "负债收入比",
+            Constraint::DownPayment => "首付比例",
+        }
+    }
+}
+
+// 一种还款方式下的评估结果.
+#[derive(Clone,Debug,PartialEq)]
+pub struct AffordabilityResult{
+    pub loan_type: LoanType,
+    // 可承受的最高房价.
+    pub max_price: f64,
+    // 对应的贷款金额.
+    pub max_loan: f64,
+    // 首月月供(基本单位)，等额本金为最高的一期.
+    pub first_payment: f64,
+    // 负债收入比允许的最高房价.
+    pub price_by_income: f64,
+    // 首付比例允许的最高房价.
+    pub price_by_down_payment: f64,
+    // 起限制作用的条件.
+    pub binding: Constraint,
+}
+
+// 购房能力输入中出错的字段.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum AffordabilityField{
+    Income,
+    Debts,
+    DownPayment,
+    DebtToIncome,
+    DownPaymentRatio,
+    Year,
+    Rate,
+}
+
+// 购房能力输入的校验错误.
+#[derive(Clone,Debug,PartialEq)]
+pub enum AffordabilityError{
+    // 月收入不大于 0.
+    InvalidIncome,
+    // 现有负债为负数，或已达到负债收入比的上限.
+    InvalidDebts,
+    // 首付款为负数.
+    InvalidDownPayment,
+    // 负债收入比不在 (0, 100] 内.
+    InvalidDebtToIncome,
+    // 首付比例不在 (0, 100] 内.
+    InvalidDownPaymentRatio,
+    InvalidYear,
+    InvalidRate,
+}
+
+impl AffordabilityError{
+    pub fn field(&self) -> AffordabilityField{
+        match self{
+            AffordabilityError::InvalidIncome => AffordabilityField::Income,
+            AffordabilityError::InvalidDebts => AffordabilityField::Debts,
+            AffordabilityError::InvalidDownPayment => AffordabilityField::DownPayment,
+            AffordabilityError::InvalidDebtToIncome => AffordabilityField::DebtToIncome,
+            AffordabilityError::InvalidDownPaymentRatio => AffordabilityField::DownPaymentRatio,
+            AffordabilityError::InvalidYear => AffordabilityField::Year,
+            AffordabilityError::InvalidRate => AffordabilityField::Rate,
+        }
+    }
+}
+
+impl std::fmt::Display for AffordabilityError{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result{
+        match self{
+            AffordabilityError::InvalidIncome => write!(f, "月收入必须大于 0"),
+            AffordabilityError::InvalidDebts => write!(f, "现有负债不能为负数，且必须低于负债收入比允许的月还款额"),
+            AffordabilityError::InvalidDownPayment => write!(f, "首付款不能为负数"),
+            AffordabilityError::InvalidDebtToIncome => write!(f, "负债收入比必须在 0% 到 100% 之间"),
+            AffordabilityError::InvalidDownPaymentRatio => write!(f, "首付比例必须在 0% 到 100% 之间"),
+            AffordabilityError::InvalidYear => write!(f, "贷款年限必须在 1 个月到 {} 年之间", loan::MAX_YEAR),
+            AffordabilityError::InvalidRate => write!(f, "贷款利率必须在 0% 到 {}% 之间", loan::MAX_RATE),
+        }
+    }
+}
+
+#[derive(Clone,PartialEq)]
+pub struct Affordability{
+    // 首付款、房价与贷款金额的单位.
+    pub unit: currency::MoneyUnit,
+    // 税后月收入(基本单位).
+    pub monthly_income: f64,
+    // 现有的每月负债还款，例如车贷、信用卡(基本单位).
+    pub monthly_debts: f64,
+    // 可用于首付的资金.
+    pub down_payment: f64,
+    // 负债收入比上限(%)：月供与现有负债之和占月收入的比例.
+    pub max_debt_to_income: f64,
+    // 最低首付比例(%).
+    pub min_down_payment_ratio: f64,
+    pub loan_year: f64,
+    // 年利率(%).
+    pub loan_rate: f64,
+    // 等额本息和等额本金的评估结果.
+    pub results: Vec<AffordabilityResult>,
+}
+
+impl Default for Affordability{
+    fn default() -> Self{
+        Self::new()
+    }
+}
+
+impl Affordability{
+    pub fn new() -> Self{
+        Self{
+            unit: currency::MoneyUnit::Wan,
+            monthly_income: 0.0,
+            monthly_debts: 0.0,
+            down_payment: 0.0,
+            max_debt_to_income: 50.0,
+            min_down_payment_ratio: 30.0,
+            loan_year: 30.0,
+            loan_rate: 0.0,
+            results: Vec::new(),
+        }
+    }
+
+    // 切换金额单位，同一货币内换算首付款.
+    pub fn set_unit(&mut self, unit: currency::MoneyUnit){
+        self.down_payment *= self.unit.conversion_to(unit);
+        self.unit = unit;
+        self.results.clear();
+    }
+
+    // 负债收入比允许的最高月供(基本单位).
+    pub fn max_monthly_payment(&self) -> f64{
+        self.monthly_income * self.max_debt_to_income / 100.0 - self.monthly_debts
+    }
+
+    // 校验输入，返回所有出错字段的错误.
+    pub fn validate(&self) -> Result<(), Vec<AffordabilityError>>{
+        let mut errors = Vec::new();
+        if self.monthly_income <= 0.0 {
+            errors.push(AffordabilityError::InvalidIncome);
+        }
+        let ratio_valid = |ratio: f64| ratio > 0.0 && ratio <= 100.0;
+        if !ratio_valid(self.max_debt_to_income) {
+            errors.push(AffordabilityError::InvalidDebtToIncome);
+        } else if self.monthly_income > 0.0 && (self.monthly_debts < 0.0 || self.max_monthly_payment() <= 0.0) {
+            errors.push(AffordabilityError::InvalidDebts);
+        }
+        if self.down_payment < 0.0 {
+            errors.push(AffordabilityError::InvalidDownPayment);
+        }
+        if !ratio_valid(self.min_down_payment_ratio) {
+            errors.push(AffordabilityError::InvalidDownPaymentRatio);
+        }
+        let month = (self.loan_year * 12.0).round();
+        if !(1.0..=loan::MAX_YEAR * 12.0).contains(&month) {
+            errors.push(AffordabilityError::InvalidYear);
+        }
+        if !(0.0..=loan::MAX_RATE).contains(&self.loan_rate) {
+            errors.push(AffordabilityError::InvalidRate);
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    // 按等额本息和等额本金分别计算。输入无效时清空结果并返回错误.
+    pub fn calc(&mut self) -> Result<(), Vec<AffordabilityError>>{
+        self.results.clear();
+        self.validate()?;
+
+        let max_payment = self.unit.from_base(self.max_monthly_payment());
+        // 首付款全部用于首付时，按最低首付比例可购买的房价.
+        let price_by_down_payment = self.down_payment / (self.min_down_payment_ratio / 100.0);
+        for loan_type in [LoanType::EqualInterest, LoanType::EqualPrincipal] {
+            let template = Loan{
+                loan_type,
+                loan_year: self.loan_year,
+                loan_rate: self.loan_rate,
+                unit: self.unit,
+                ..Loan::new()
+            };
+            // 首期还款额随贷款金额单调递增，超过上限时按上限计.
+            let loan_by_income = loan::solve_money(&template, max_payment).unwrap_or_else(|| template.max_money());
+            let price_by_income = loan_by_income + self.down_payment;
+            let (max_price, binding) = if price_by_income <= price_by_down_payment {
+                (price_by_income, Constraint::DebtToIncome)
+            } else {
+                (price_by_down_payment, Constraint::DownPayment)
+            };
+            let max_loan = max_price - self.down_payment;
+            let first_payment = self.unit.to_base(loan::first_payment(&Loan{ loan_money: max_loan, ..template }));
+            self.results.push(AffordabilityResult{
+                loan_type,
+                max_price,
+                max_loan,
+                first_payment,
+                price_by_income,
+                price_by_down_payment,
+                binding,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn reset(&mut self){
+        *self = Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::currency::MoneyUnit;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64){
+        assert!((actual - expected).abs() < tolerance, "{} != {}", actual, expected);
+    }
+
+    // 月收入 2 万元、现有负债 2000 元，负债收入比 50%，30 年、年利率 4.9%.
+    fn household() -> Affordability{
+        Affordability{
+            monthly_income: 20_000.0,
+            monthly_debts: 2000.0,
+            down_payment: 100.0,
+            loan_rate: 4.9,
+            ..Affordability::new()
+        }
+    }
+
+    #[test]
+    fn max_loan_from_income(){
+        let mut model = household();
+        assert_close(model.max_monthly_payment(), 8000.0, 1e-9);
+        model.calc().unwrap();
+        let [interest, principal] = &model.results[..] else { panic!("应有两种还款方式的结果") };
+        assert_eq!(interest.binding, Constraint::DebtToIncome);
+        assert_close(interest.max_loan, 150.736710, 1e-5);
+        assert_close(interest.max_price, 250.736710, 1e-5);
+        assert_close(interest.first_payment, 8000.0, 1e-4);
+        // 等额本金的首月月供最高，可贷金额更少.
+        assert_close(principal.max_loan, 116.599190, 1e-5);
+        assert_close(principal.first_payment, 8000.0, 1e-4);
+        assert_close(principal.price_by_down_payment, 100.0 / 0.3, 1e-9);
+    }
+
+    #[test]
+    fn down_payment_binds(){
+        let mut model = Affordability{ down_payment: 50.0, ..household() };
+        model.calc().unwrap();
+        let result = &model.results[0];
+        assert_eq!(result.binding, Constraint::DownPayment);
+        assert_close(result.max_price, 50.0 / 0.3, 1e-9);
+        assert_close(result.max_loan, 50.0 / 0.3 - 50.0, 1e-9);
+    }
+
+    #[test]
+    fn units(){
+        let mut model = household();
+        model.calc().unwrap();
+        let wan = model.results[0].clone();
+        // 同一货币内换算首付款，结果按新单位计.
+        model.set_unit(MoneyUnit::Yuan);
+        assert_close(model.down_payment, 1_000_000.0, 1e-6);
+        model.calc().unwrap();
+        assert_close(model.results[0].max_loan, wan.max_loan * currency::WAN, 1e-2);
+        assert_close(model.results[0].first_payment, wan.first_payment, 1e-4);
+        // 不同货币之间不换算金额.
+        model.set_unit(MoneyUnit::Dollar);
+        assert_close(model.down_payment, 1_000_000.0, 1e-6);
+        assert!(model.results.is_empty());
+    }
+
+    #[test]
+    fn invalid_input(){
+        let mut model = Affordability{ monthly_debts: 10_000.0, loan_year: 0.0, ..household() };
+        assert_eq!(model.calc(), Err(vec![AffordabilityError::InvalidDebts, AffordabilityError::InvalidYear]));
+    }
+}
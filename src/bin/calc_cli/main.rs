@@ -0,0 +1,183 @@
+// 命令行版本的计算器，不依赖图形界面。
+//
+// 用法:
+//   calc_cli [表达式 ...]      计算每个参数中的表达式；没有参数时从标准输入逐行读取
+//   calc_cli repl              启动交互式计算器
+//   calc_cli loan --years <年> --amount <金额> --rate <%> [--unit <单位>] [--type <方式>] [--graduation <增幅>]
+//                 [--frequency <频率>] [--compounding <频率>] [--rate-type nominal|effective]
+
+// 交互式计算器依赖 rustyline，网页版本不可用.
+#[cfg(not(target_arch = "wasm32"))]
+mod repl;
+
+use std::io::BufRead;
+use std::process::ExitCode;
+
+use rust1::{currency, loan, math_exp, rate};
+
+const USAGE: &str = "用法:
+  calc_cli [表达式 ...]      计算每个参数中的表达式；没有参数时从标准输入逐行读取
+  calc_cli repl              启动交互式计算器
+  calc_cli loan --years <年> --amount <金额> --rate <%> [--unit <单位>] [--type <方式>] [--graduation <增幅>]
+                 [--frequency <频率>] [--compounding <频率>] [--rate-type nominal|effective]
+
+金额单位: wan(万元，默认) yuan(元) usd eur gbp jpy hkd，不同货币之间不做汇率换算
+还款方式: interest(等额本息，默认) principal(等额本金) interest-only(先息后本) lump-sum(到期还本付息)
+          geometric(等比递增，增幅为 %) arithmetic(等额递增，增幅的单位与金额相同) biweekly(双周供)
+频率: daily weekly biweekly monthly(默认) quarterly semiannual annual
+表达式中的负号作用于其后的整个乘方: -2^2 = -4，(-2)^2 = 4，2^-2 = 0.25";
+
+// 计算错误时的退出码.
+const EXIT_CALC_ERROR: u8 = 1;
+// 参数错误时的退出码.
+const EXIT_USAGE: u8 = 2;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(|s| s.as_str()) {
+        Some("-h") | Some("--help") => {
+            println!("{}", USAGE);
+            ExitCode::SUCCESS
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        Some("repl") => repl::run(),
+        Some("loan") => run_loan(&args[1..]),
+        Some(_) => run_expressions(args.iter().map(|s| s.to_string())),
+        None => {
+            let stdin = std::io::stdin();
+            let lines = stdin.lock().lines().map_while(Result::ok).filter(|l| !l.trim().is_empty());
+            run_expressions(lines)
+        }
+    }
+}
+
+// 逐个计算表达式，任意一个失败时返回非零退出码.
+fn run_expressions(expressions: impl Iterator<Item = String>) -> ExitCode {
+    let mut failed = false;
+    for expression in expressions {
+        match eval(&expression) {
+            Ok(val) => println!("{}", val),
+            Err(e) => {
+                eprintln!("错误: {}: {}", expression, e);
+                failed = true;
+            }
+        }
+    }
+    if failed { ExitCode::from(EXIT_CALC_ERROR) } else { ExitCode::SUCCESS }
+}
+
+fn eval(expression: &str) -> Result<f64, String> {
+    let mut math_exp = math_exp::MathExp::new();
+    math_exp.input(expression)?;
+    math_exp.try_calculate()
+}
+
+fn run_loan(args: &[String]) -> ExitCode {
+    let mut loan = loan::Loan::new();
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let value = match iter.next() {
+            Some(v) => v,
+            None => return usage_error(&format!("{} 缺少参数值", flag)),
+        };
+        let parsed = match flag.as_str() {
+            "--years" => value.parse().map(|v| loan.loan_year = v),
+            "--amount" => value.parse().map(|v| loan.loan_money = v),
+            "--rate" => value.parse().map(|v| loan.loan_rate = v),
+            "--graduation" => value.parse().map(|v| loan.graduation = v),
+            // 金额按最终选择的单位解释，与参数的先后顺序无关.
+            "--unit" => {
+                match parse_unit(value) {
+                    Some(unit) => loan.unit = unit,
+                    None => return usage_error(&format!("未知的金额单位: {}", value)),
+                }
+                Ok(())
+            }
+            "--frequency" | "--compounding" => {
+                let frequency = match parse_frequency(value) {
+                    Some(f) => f,
+                    None => return usage_error(&format!("未知的频率: {}", value)),
+                };
+                if flag == "--frequency" {
+                    loan.payment_frequency = frequency;
+                } else {
+                    loan.compounding = frequency;
+                }
+                Ok(())
+            }
+            "--rate-type" => {
+                match value.as_str() {
+                    "nominal" => loan.rate_convention = rate::RateConvention::Nominal,
+                    "effective" => loan.rate_convention = rate::RateConvention::Effective,
+                    _ => return usage_error(&format!("未知的利率类型: {}", value)),
+                }
+                Ok(())
+            }
+            "--type" => {
+                match value.as_str() {
+                    "interest" => loan.loan_type = loan::LoanType::EqualInterest,
+                    "principal" => loan.loan_type = loan::LoanType::EqualPrincipal,
+                    "interest-only" => loan.loan_type = loan::LoanType::InterestOnly,
+                    "lump-sum" => loan.loan_type = loan::LoanType::LumpSum,
+                    "geometric" => loan.loan_type = loan::LoanType::GraduatedGeometric,
+                    "arithmetic" => loan.loan_type = loan::LoanType::GraduatedArithmetic,
+                    "biweekly" => loan.loan_type = loan::LoanType::BiWeekly,
+                    _ => return usage_error(&format!("未知的还款方式: {}", value)),
+                }
+                Ok(())
+            }
+            _ => return usage_error(&format!("未知的参数: {}", flag)),
+        };
+        if parsed.is_err() {
+            return usage_error(&format!("{} 的值无效: {}", flag, value));
+        }
+    }
+
+    if let Err(errors) = loan.calc() {
+        for e in errors {
+            eprintln!("错误: {}", e);
+        }
+        return ExitCode::from(EXIT_USAGE);
+    }
+    let result = loan.result.unwrap_or_default();
+    let unit = loan.unit;
+    let money = |value: f64| currency::format_amount(unit.to_base(value), unit.decimals());
+    let base = unit.base();
+    println!("首月还款({}): {}", base.to_str(), money(result.first_payment));
+    println!("末月还款({}): {}", base.to_str(), money(result.last_payment));
+    println!("月均还款({}): {}", base.to_str(), money(result.average_payment));
+    println!("利息总额({}): {}", base.to_str(), money(result.total_interest));
+    println!("还款总额({}): {}", base.to_str(), money(result.total_paid));
+    ExitCode::SUCCESS
+}
+
+fn parse_unit(name: &str) -> Option<currency::MoneyUnit> {
+    match name {
+        "yuan" => Some(currency::MoneyUnit::Yuan),
+        "wan" => Some(currency::MoneyUnit::Wan),
+        "usd" => Some(currency::MoneyUnit::Dollar),
+        "eur" => Some(currency::MoneyUnit::Euro),
+        "gbp" => Some(currency::MoneyUnit::Pound),
+        "jpy" => Some(currency::MoneyUnit::Yen),
+        "hkd" => Some(currency::MoneyUnit::HongKongDollar),
+        _ => None,
+    }
+}
+
+fn parse_frequency(name: &str) -> Option<rate::Frequency> {
+    match name {
+        "daily" => Some(rate::Frequency::Daily),
+        "weekly" => Some(rate::Frequency::Weekly),
+        "biweekly" => Some(rate::Frequency::BiWeekly),
+        "monthly" => Some(rate::Frequency::Monthly),
+        "quarterly" => Some(rate::Frequency::Quarterly),
+        "semiannual" => Some(rate::Frequency::SemiAnnual),
+        "annual" => Some(rate::Frequency::Annual),
+        _ => None,
+    }
+}
+
+fn usage_error(message: &str) -> ExitCode {
+    eprintln!("错误: {}\n{}", message, USAGE);
+    ExitCode::from(EXIT_USAGE)
+}
@@ -0,0 +1,160 @@
+// 交互式终端计算器，与图形界面共用 math_exp 的计算逻辑。
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+
+use rust1::{math_exp, token};
+
+const HELP: &str = "输入表达式并回车计算，行尾输入 \\ 可以换行继续输入。
+  x = 表达式   将结果保存到变量 x（变量名只能由字母组成）
+  Ans          上一次计算的结果
+  :deg / :rad  三角函数使用角度 / 弧度
+  :vars        列出所有变量
+  :clear       清除所有变量和 Ans
+  :help        显示帮助
+  :quit        退出";
+
+const HISTORY_FILE: &str = ".calc_history";
+
+// 保存上一次计算结果的变量名.
+const ANS: &str = "Ans";
+
+struct Repl {
+    settings: math_exp::Settings,
+    variables: BTreeMap<String, f64>,
+}
+
+pub fn run() -> ExitCode {
+    let mut editor = match DefaultEditor::new() {
+        Ok(editor) => editor,
+        Err(e) => {
+            eprintln!("错误: 无法初始化终端: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let history = history_path();
+    if let Some(path) = &history {
+        // 第一次运行时历史文件不存在.
+        let _ = editor.load_history(path);
+    }
+
+    let mut repl = Repl {
+        settings: math_exp::Settings::default(),
+        variables: BTreeMap::new(),
+    };
+    println!("输入 :help 查看帮助");
+
+    let mut pending = String::new();
+    loop {
+        let prompt = if pending.is_empty() { "> " } else { "… " };
+        match editor.readline(prompt) {
+            Ok(line) => {
+                // 行尾的 \ 表示表达式在下一行继续.
+                if let Some(part) = line.trim_end().strip_suffix('\\') {
+                    pending.push_str(part);
+                    continue;
+                }
+                pending.push_str(&line);
+                let input = std::mem::take(&mut pending);
+                if input.trim().is_empty() { continue; }
+                let _ = editor.add_history_entry(input.as_str());
+                if !repl.handle(input.trim()) { break; }
+            }
+            // Ctrl-C 放弃当前输入.
+            Err(ReadlineError::Interrupted) => { pending.clear(); }
+            Err(ReadlineError::Eof) => { break; }
+            Err(e) => {
+                eprintln!("错误: {}", e);
+                break;
+            }
+        }
+    }
+
+    if let Some(path) = &history {
+        if let Err(e) = editor.save_history(path) {
+            eprintln!("错误: 无法保存历史记录: {}", e);
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(HISTORY_FILE))
+}
+
+impl Repl {
+    // 处理一条输入，返回 false 表示退出.
+    fn handle(&mut self, input: &str) -> bool {
+        match input {
+            ":quit" | ":q" => { return false; }
+            ":help" => { println!("{}", HELP); }
+            ":deg" => {
+                self.settings.angle_mode = math_exp::AngleMode::Degree;
+                println!("三角函数使用角度");
+            }
+            ":rad" => {
+                self.settings.angle_mode = math_exp::AngleMode::Radian;
+                println!("三角函数使用弧度");
+            }
+            ":vars" => {
+                if self.variables.is_empty() { println!("没有变量"); }
+                for (name, val) in &self.variables {
+                    println!("{} = {}", name, val);
+                }
+            }
+            ":clear" => {
+                self.variables.clear();
+                println!("已清除所有变量");
+            }
+            _ if input.starts_with(':') => { eprintln!("错误: 未知的命令 {}，输入 :help 查看帮助", input); }
+            _ => {
+                let (name, expression) = match split_assignment(input) {
+                    Ok(parts) => parts,
+                    Err(e) => {
+                        eprintln!("错误: {}", e);
+                        return true;
+                    }
+                };
+                match self.eval(expression) {
+                    Ok(val) => {
+                        if let Some(name) = name {
+                            self.variables.insert(name.to_string(), val);
+                            println!("{} = {}", name, val);
+                        } else {
+                            println!("{}", val);
+                        }
+                        self.variables.insert(ANS.to_string(), val);
+                    }
+                    Err(e) => { eprintln!("错误: {}", e); }
+                }
+            }
+        }
+        true
+    }
+
+    fn eval(&self, expression: &str) -> Result<f64, String> {
+        let mut math_exp = math_exp::MathExp::new();
+        math_exp.settings = self.settings.clone();
+        math_exp.input_with_variables(expression, &self.variables)?;
+        math_exp.try_calculate()
+    }
+}
+
+// 拆分 "x = 表达式" 形式的赋值语句.
+fn split_assignment(input: &str) -> Result<(Option<&str>, &str), String> {
+    let (name, expression) = match input.split_once('=') {
+        Some((name, expression)) => (name.trim(), expression),
+        None => return Ok((None, input)),
+    };
+    let reserved = name == ANS || name == "sqrt" || token::Func::try_from(name).is_ok();
+    if name.is_empty() || !name.chars().all(char::is_alphabetic) || reserved {
+        return Err(format!("无效的变量名: {}", name));
+    }
+    Ok((Some(name), expression))
+}
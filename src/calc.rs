@@ -6,12 +6,19 @@ use egui::Color32;
 
 //use egui_extras::*;
 
-use rust1::{calc_config, calc_state, keyboard, loan, math_exp};
+use rust1::{affordability, calc_config, calc_state, cashflow, currency, date, export, keyboard, loan, math_exp, rate, savings, scenario, tvm};
 
 pub(crate) struct CalcApp {
     pub(crate) ui_state: calc_state::UiState,
     pub(crate) math_exp: math_exp::MathExp,
     pub loan: loan::Loan,
+    pub combination: loan::CombinationLoan,
+    pub comparison: loan::Comparison,
+    pub savings: savings::Savings,
+    pub tvm: tvm::Tvm,
+    pub cash_flows: cashflow::CashFlows,
+    pub affordability: affordability::Affordability,
+    pub scenarios: scenario::Scenarios,
 }
 
 pub const PRIMARY_COLOR: egui::Color32 = egui::Color32::from_rgb(25, 95, 200);
@@ -28,7 +35,11 @@ impl CalcApp {
         let target = match self.ui_state.page{
             calc_state::Nav::Standard => Rect::from_min_size(line_root, vec2(line_width, line_height)),
             calc_state::Nav::Loan => Rect::from_min_size(line_root + vec2(0.0, 42.0), vec2(line_width, line_height)),
-            calc_state::Nav::Settings => Rect::from_min_size(line_root + vec2(0.0, 84.0), vec2(line_width, line_height)),
+            calc_state::Nav::Savings => Rect::from_min_size(line_root + vec2(0.0, 84.0), vec2(line_width, line_height)),
+            calc_state::Nav::Tvm => Rect::from_min_size(line_root + vec2(0.0, 126.0), vec2(line_width, line_height)),
+            calc_state::Nav::CashFlow => Rect::from_min_size(line_root + vec2(0.0, 168.0), vec2(line_width, line_height)),
+            calc_state::Nav::Affordability => Rect::from_min_size(line_root + vec2(0.0, 210.0), vec2(line_width, line_height)),
+            calc_state::Nav::Settings => Rect::from_min_size(line_root + vec2(0.0, 252.0), vec2(line_width, line_height)),
         };
         let mut current = self.ui_state.current_menu_line_rect.unwrap_or(target);
         if self.ui_state.at.menu_change > 0.0{
@@ -76,14 +87,42 @@ impl CalcApp {
                 let result = self.math_exp.get_output();
                 let result_length = result.chars().count() as f32;
                 let expression = self.math_exp.to_string();
-                let expression_length = expression.chars().count() as f32;
+                let expression_length = expression.chars().count();
+                let ghost_parens = self.math_exp.ghost_parens();
+                let matching_parens = self.math_exp.matching_parens();
+                let preview = self.math_exp.preview().unwrap_or_default();
+
+                // 未闭合的括号以灰色补全显示，匹配的一对括号高亮显示
+                let font_id = egui::FontId::monospace(size_font((expression_length + ghost_parens) as f32));
+                let mut job = egui::text::LayoutJob::default();
+                let full_expression = expression + &")".repeat(ghost_parens);
+                for (i, c) in full_expression.chars().enumerate() {
+                    let mut format = egui::TextFormat {
+                        font_id: font_id.clone(),
+                        color: ui.visuals().text_color(),
+                        ..Default::default()
+                    };
+                    if i >= expression_length {
+                        format.color = egui::Color32::GRAY;
+                    }
+                    if matching_parens.is_some_and(|(l, r)| i == l || i == r) {
+                        format.color = PRIMARY_COLOR_HOVERED;
+                        format.background = egui::Color32::from_gray(60);
+                    }
+                    job.append(&c.to_string(), 0.0, format);
+                }
+                ui.add_sized(
+                    [330.0, 55.0],
+                    egui::Label::new(job).wrap(true),
+                );
+                // 输入过程中的预览结果
                 ui.add_sized(
-                    [330.0, 70.0],
+                    [330.0, 15.0],
                     egui::Label::new(
-                        egui::RichText::new(expression)
-                            .font(egui::FontId::monospace(size_font(expression_length))),
-                    )
-                    .wrap(true),
+                        egui::RichText::new(preview)
+                            .font(egui::FontId::monospace(14.0))
+                            .color(egui::Color32::GRAY),
+                    ),
                 );
                 ui.add_sized(
                     [330.0, 75.0],
@@ -103,151 +142,1020 @@ impl CalcApp {
         );
     }
 
-    fn loan_ui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) {
+    fn loan_ui(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            for view in [calc_state::LoanView::Single, calc_state::LoanView::Combination, calc_state::LoanView::Comparison] {
+                ui.selectable_value(&mut self.ui_state.loan_view, view, egui::RichText::new(view.to_str()).size(15.0));
+            }
+        });
+        ui.separator();
+        match self.ui_state.loan_view {
+            calc_state::LoanView::Single => self.single_loan_ui(ctx, ui),
+            calc_state::LoanView::Combination => self.combination_loan_ui(ui),
+            calc_state::LoanView::Comparison => self.comparison_ui(ui),
+        }
+    }
+
+    fn comparison_ui(&mut self, ui: &mut egui::Ui) {
+        egui::ScrollArea::both().show(ui, |ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(8.0, 8.0);
+            ui.horizontal(|ui| {
+                ui.label("金额单位:");
+                if let Some(unit) = money_unit_ui(ui, "comparison_unit", self.comparison.unit) {
+                    self.comparison.set_unit(unit);
+                }
+            });
+            let unit = self.comparison.unit;
+            ui.horizontal(|ui| {
+                ui.label(format!("贷款金额({}):", unit.to_str()));
+                let max_money = self.comparison.max_money();
+                ui.add(egui::DragValue::new(&mut self.comparison.loan_money)
+                    .clamp_range(0.0..=max_money)
+                    .speed(1.0)
+                    .max_decimals(4));
+                if ui.button("使用单一贷款的输入").clicked() {
+                    self.comparison.copy_inputs(&self.loan);
+                }
+            });
+
+            egui::Grid::new("comparison_inputs").num_columns(3).show(ui, |ui| {
+                ui.label("");
+                ui.strong("方案一");
+                ui.strong("方案二");
+                ui.end_row();
+                ui.label("还款方式:");
+                for (i, scenario) in self.comparison.loans.iter_mut().enumerate() {
+                    egui::ComboBox::from_id_source(("comparison_type", i))
+                        .selected_text(scenario.loan_type.to_str())
+                        .width(100.0)
+                        .show_ui(ui, |ui| {
+                            for loan_type in loan::LoanType::MONTHLY {
+                                ui.selectable_value(&mut scenario.loan_type, loan_type, loan_type.to_str());
+                            }
+                        });
+                }
+                ui.end_row();
+                ui.label("贷款年限(年):");
+                for scenario in self.comparison.loans.iter_mut() {
+                    ui.add(egui::DragValue::new(&mut scenario.loan_year)
+                        .clamp_range(0.0..=loan::MAX_YEAR)
+                        .speed(0.1)
+                        .max_decimals(2));
+                }
+                ui.end_row();
+                ui.label("贷款利率(%):");
+                for scenario in self.comparison.loans.iter_mut() {
+                    ui.add(egui::DragValue::new(&mut scenario.loan_rate)
+                        .clamp_range(0.0..=loan::MAX_RATE)
+                        .speed(0.01)
+                        .max_decimals(3));
+                }
+                ui.end_row();
+            });
+
+            if ui.button("计算").clicked() {
+                self.ui_state.comparison_errors = self.comparison.calc().err().unwrap_or_default();
+            }
+            for (i, e) in &self.ui_state.comparison_errors {
+                ui.colored_label(egui::Color32::LIGHT_RED, format!("方案{}: {}", ["一", "二"][*i], e));
+            }
+
+            let [first, second] = &self.comparison.loans;
+            let (Some(a), Some(b)) = (&first.result, &second.result) else {
+                return;
+            };
+            egui::Grid::new("comparison_results").num_columns(4).striped(true).show(ui, |ui| {
+                for title in ["", "方案一", "方案二", "差额(二 - 一)"] {
+                    ui.strong(title);
+                }
+                ui.end_row();
+                let base = unit.base();
+                for (title, x, y) in [
+                    ("首月还款", a.first_payment, b.first_payment),
+                    ("末月还款", a.last_payment, b.last_payment),
+                    ("月均还款", a.average_payment, b.average_payment),
+                    ("利息总额", a.total_interest, b.total_interest),
+                    ("还款总额", a.total_paid, b.total_paid),
+                ] {
+                    ui.label(format!("{}({})", title, base.to_str()));
+                    ui.label(unit.format(x));
+                    ui.label(unit.format(y));
+                    let diff = y - x;
+                    let color = if diff > 0.0 { egui::Color32::LIGHT_RED } else { egui::Color32::LIGHT_GREEN };
+                    ui.colored_label(color, unit.format(diff));
+                    ui.end_row();
+                }
+            });
+
+            // 月供走势
+            ui.label(egui::RichText::new(format!("月供走势({}):", unit.base().to_str())).size(16.0));
+            egui::plot::Plot::new("comparison_trajectory")
+                .height(200.0)
+                .legend(egui::plot::Legend::default())
+                .allow_scroll(false)
+                .show(ui, |plot_ui| {
+                    for (name, scenario) in [("方案一", first), ("方案二", second)] {
+                        let points: Vec<[f64; 2]> = scenario.schedule.iter()
+                            .map(|p| [p.period as f64, unit.to_base(p.payment)])
+                            .collect();
+                        plot_ui.line(egui::plot::Line::new(points).name(name));
+                    }
+                });
+        });
+    }
+
+    fn combination_loan_ui(&mut self, ui: &mut egui::Ui) {
+        egui::ScrollArea::both().show(ui, |ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(8.0, 8.0);
+            ui.horizontal(|ui| {
+                ui.label("金额单位:");
+                if let Some(unit) = money_unit_ui(ui, "combination_unit", self.combination.unit) {
+                    self.combination.set_unit(unit);
+                }
+            });
+            let unit = self.combination.unit;
+            let mut removed = None;
+            egui::Grid::new("tranches").num_columns(6).striped(true).show(ui, |ui| {
+                let money = format!("金额({})", unit.to_str());
+                for title in ["名称", "还款方式", "年限(年)", money.as_str(), "利率(%)", ""] {
+                    ui.strong(title);
+                }
+                ui.end_row();
+                for (i, tranche) in self.combination.tranches.iter_mut().enumerate() {
+                    ui.add(egui::TextEdit::singleline(&mut tranche.name).desired_width(80.0));
+                    egui::ComboBox::from_id_source(("tranche_type", i))
+                        .selected_text(tranche.loan.loan_type.to_str())
+                        .width(100.0)
+                        .show_ui(ui, |ui| {
+                            for loan_type in loan::LoanType::MONTHLY {
+                                ui.selectable_value(&mut tranche.loan.loan_type, loan_type, loan_type.to_str());
+                            }
+                        });
+                    ui.add(egui::DragValue::new(&mut tranche.loan.loan_year)
+                        .clamp_range(0.0..=loan::MAX_YEAR)
+                        .speed(0.1)
+                        .max_decimals(2));
+                    let max_money = tranche.loan.max_money();
+                    ui.add(egui::DragValue::new(&mut tranche.loan.loan_money)
+                        .clamp_range(0.0..=max_money)
+                        .speed(1.0)
+                        .max_decimals(4));
+                    ui.add(egui::DragValue::new(&mut tranche.loan.loan_rate)
+                        .clamp_range(0.0..=loan::MAX_RATE)
+                        .speed(0.01)
+                        .max_decimals(3));
+                    if ui.small_button("删除").clicked() {
+                        removed = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some(i) = removed {
+                self.combination.tranches.remove(i);
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button("添加贷款").clicked() {
+                    let name = format!("贷款 {}", self.combination.tranches.len() + 1);
+                    self.combination.tranches.push(loan::Tranche{
+                        name,
+                        loan: loan::Loan{ unit, ..loan::Loan::new() },
+                    });
+                }
+                if ui.button("计算").clicked() {
+                    self.ui_state.combination_errors = self.combination.calc().err().unwrap_or_default();
+                }
+                if ui.button("重置").clicked() {
+                    self.combination.reset();
+                    self.ui_state.combination_errors.clear();
+                }
+            });
+            for (i, e) in &self.ui_state.combination_errors {
+                let name = self.combination.tranches.get(*i).map_or("", |t| t.name.as_str());
+                ui.colored_label(egui::Color32::LIGHT_RED, format!("{}: {}", name, e));
+            }
+
+            loan_result_ui(ui, self.combination.result.as_ref(), unit);
+
+            // 各笔贷款的明细
+            if self.combination.result.is_some() {
+                egui::Grid::new("tranche_results").num_columns(4).striped(true).show(ui, |ui| {
+                    ui.strong("名称");
+                    for title in ["首月还款", "利息总额", "还款总额"] {
+                        ui.strong(format!("{}({})", title, unit.base().to_str()));
+                    }
+                    ui.end_row();
+                    for tranche in &self.combination.tranches {
+                        if let Some(result) = &tranche.loan.result {
+                            ui.label(&tranche.name);
+                            ui.label(unit.format(result.first_payment));
+                            ui.label(unit.format(result.total_interest));
+                            ui.label(unit.format(result.total_paid));
+                            ui.end_row();
+                        }
+                    }
+                });
+            }
+
+            if !self.combination.schedule.is_empty() {
+                ui.label(egui::RichText::new(format!("合并还款计划({}):", unit.base().to_str())).size(16.0));
+                schedule_charts(ui, "combination_charts", &self.combination.schedule, unit);
+                schedule_table(ui, "combination_schedule", &self.combination.schedule, unit);
+            }
+        });
+    }
+
+    fn single_loan_ui(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) {
         let space = 2.0;
         let calc_size = egui::vec2(500.0, 600.0);
 
-        ui.allocate_ui_with_layout(
-            calc_size,
-            egui::Layout::top_down(egui::Align::Min),
-            |ui| {
-                let temp_space = egui::vec2(20.0, 20.0);
-                ui.spacing_mut().item_spacing = temp_space;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.allocate_ui_with_layout(
+                calc_size,
+                egui::Layout::top_down(egui::Align::Min),
+                |ui| {
+                    let temp_space = egui::vec2(20.0, 20.0);
+                    ui.spacing_mut().item_spacing = temp_space;
 
-                // 上半部分
-                ui.allocate_ui_with_layout(
-                    ui.available_size(),
-                    egui::Layout::left_to_right(egui::Align::Min),
-                    |ui| {
-                        ui.vertical(|ui| {
-                            // 竖直方向上标签间距
-                            ui.label(egui::RichText::new(
-                                "🔥还款方式：")
-                            .size(16.0)
-                            );
+                    self.scenarios_ui(ui);
 
-                            ui.label(egui::RichText::new("贷款年限(年):").size(16.0));
-                            ui.label(egui::RichText::new("贷款金额(万元):").size(16.0));
-                            ui.label(egui::RichText::new("贷款利率(%):").size(16.0));
+                    // 求解模式：正向计算月供，或根据目标月供反求利率、年限、金额
+                    ui.horizontal(|ui| {
+                        ui.spacing_mut().item_spacing.x = 8.0;
+                        ui.label(egui::RichText::new("求解:").size(16.0));
+                        for unknown in [loan::SolveFor::Payment, loan::SolveFor::Rate, loan::SolveFor::Year, loan::SolveFor::Money] {
+                            ui.selectable_value(&mut self.ui_state.solve_for, unknown, unknown.to_str());
+                        }
+                    });
+                    // 金额单位，切换时换算已输入的金额
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new("金额单位:").size(16.0));
+                        if let Some(unit) = money_unit_ui(ui, "loan_unit", self.loan.unit) {
+                            self.loan.set_unit(unit);
+                        }
+                    });
+                    let unit = self.loan.unit;
+                    if self.ui_state.solve_for != loan::SolveFor::Payment {
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new(format!("目标首月还款({}):", unit.base().to_str())).size(16.0));
+                            let payment = ui.add(egui::DragValue::new(&mut self.ui_state.target_payment)
+                                .clamp_range(0.0..=f64::MAX)
+                                .speed(10.0)
+                                .max_decimals(2));
+                            mark_error(ui, self.ui_state.loan_errors.iter().any(|e| e.field() == loan::LoanField::Payment), &payment);
                         });
+                    }
+                    let unknown = self.ui_state.solve_for.field();
+
+                    // 上半部分
+                    ui.allocate_ui_with_layout(
+                        ui.available_size(),
+                        egui::Layout::left_to_right(egui::Align::Min),
+                        |ui| {
+                            ui.vertical(|ui| {
+                                // 竖直方向上标签间距
+                                ui.label(egui::RichText::new(
+                                    "🔥还款方式：")
+                                .size(16.0)
+                                );
+
+                                ui.label(egui::RichText::new("贷款年限(年):").size(16.0));
+                                ui.label(egui::RichText::new(format!("贷款金额({}):", unit.to_str())).size(16.0));
+                                ui.label(egui::RichText::new("贷款利率(%):").size(16.0));
+                                if self.loan.loan_type.is_graduated() {
+                                    let label = match self.loan.loan_type {
+                                        loan::LoanType::GraduatedGeometric => "每年增幅(%):".to_string(),
+                                        _ => format!("每年增额({}):", unit.to_str()),
+                                    };
+                                    ui.label(egui::RichText::new(label).size(16.0));
+                                }
+                            });
 
-                        ui.add_space(space);
-                        ui.vertical(|ui| {
+                            ui.add_space(space);
+                            ui.vertical(|ui| {
                             
                             
-                            ui.horizontal(|ui| {
+                                ui.horizontal(|ui| {
                                 
-                                ui.visuals_mut().widgets.inactive.weak_bg_fill = egui::Color32::from_rgb(51,0,105);
-                                ui.visuals_mut().widgets.hovered.weak_bg_fill = egui::Color32::from_rgb(102,0,205);
-                                ui.visuals_mut().widgets.active.weak_bg_fill = egui::Color32::from_rgb(51,0,105);
-                                if ui.add_sized([80.0, 19.0], egui::SelectableLabel::new(
-                                    self.loan.loan_type == loan::LoanType::EqualInterest,
-                                    egui::RichText::new("等额本息").size(15.0),
-                                )).clicked() {
-                                    self.loan.loan_type = loan::LoanType::EqualInterest;
-                                }
-                                if ui.add_sized([80.0, 19.0], egui::SelectableLabel::new(
-                                    self.loan.loan_type == loan::LoanType::EqualPrincipal,
-                                    egui::RichText::new("等额本金").size(15.0),
-                                )).clicked() {
-                                    self.loan.loan_type = loan::LoanType::EqualPrincipal;
+                                    ui.visuals_mut().widgets.inactive.weak_bg_fill = egui::Color32::from_rgb(51,0,105);
+                                    ui.visuals_mut().widgets.hovered.weak_bg_fill = egui::Color32::from_rgb(102,0,205);
+                                    ui.visuals_mut().widgets.active.weak_bg_fill = egui::Color32::from_rgb(51,0,105);
+                                    egui::ComboBox::from_id_source("loan_type")
+                                        .selected_text(egui::RichText::new(self.loan.loan_type.to_str()).size(15.0))
+                                        .width(165.0)
+                                        .show_ui(ui, |ui| {
+                                            for loan_type in loan::LoanType::ALL {
+                                                ui.selectable_value(&mut self.loan.loan_type, loan_type, egui::RichText::new(loan_type.to_str()).size(15.0));
+                                            }
+                                        });
+                                });
+
+                                let temp_space = egui::vec2(10.0, 18.0);
+                                ui.spacing_mut().item_spacing = temp_space;
+                                // 贷款年限
+                                let year = ui.add_enabled_ui(unknown != Some(loan::LoanField::Year), |ui| ui.add_sized(egui::vec2(165.0, 21.5),egui::DragValue::new(&mut self.loan.loan_year)
+                                    .clamp_range(0.0..=loan::MAX_YEAR)
+                                    .speed(0.1)
+                                    .max_decimals(2))).inner;
+                                mark_error(ui, self.ui_state.loan_errors.iter().any(|e| e.field() == loan::LoanField::Year), &year);
+                                // 贷款金额
+                                let max_money = self.loan.max_money();
+                                let money = ui.add_enabled_ui(unknown != Some(loan::LoanField::Money), |ui| ui.add_sized(egui::vec2(165.0, 21.5),egui::DragValue::new(&mut self.loan.loan_money)
+                                    .clamp_range(0.0..=max_money)
+                                    .speed(1.0)
+                                    .max_decimals(4))).inner;
+                                mark_error(ui, self.ui_state.loan_errors.iter().any(|e| e.field() == loan::LoanField::Money), &money);
+                                // 贷款利率
+                                let rate = ui.add_enabled_ui(unknown != Some(loan::LoanField::Rate), |ui| ui.add_sized(egui::vec2(165.0, 21.5),egui::DragValue::new(&mut self.loan.loan_rate)
+                                    .clamp_range(0.0..=loan::MAX_RATE)
+                                    .speed(0.01)
+                                    .max_decimals(3))).inner;
+                                mark_error(ui, self.ui_state.loan_errors.iter().any(|e| e.field() == loan::LoanField::Rate), &rate);
+                                // 递增还款的每年增幅
+                                if self.loan.loan_type.is_graduated() {
+                                    let (range, speed) = match self.loan.loan_type {
+                                        loan::LoanType::GraduatedGeometric => (loan::MAX_GRADUATION, 0.1),
+                                        // 等额递增的年增额不受贷款金额限制，反求金额时贷款金额尚未确定.
+                                        _ => (self.loan.max_money(), 0.01),
+                                    };
+                                    let graduation = ui.add_sized(egui::vec2(165.0, 21.5), egui::DragValue::new(&mut self.loan.graduation)
+                                        .clamp_range(-range..=range)
+                                        .speed(speed)
+                                        .max_decimals(4));
+                                    mark_error(ui, self.ui_state.loan_errors.iter().any(|e| e.field() == loan::LoanField::Graduation), &graduation);
                                 }
                             });
+                        },
+                    );
 
-                            let temp_space = egui::vec2(10.0, 18.0);
-                            ui.spacing_mut().item_spacing = temp_space;
-                            // 贷款年限
-                            ui.add_sized(egui::vec2(165.0, 21.5),egui::DragValue::new(&mut self.loan.loan_year));
-                            // 贷款金额
-                            ui.add_sized(egui::vec2(165.0, 21.5),egui::DragValue::new(&mut self.loan.loan_money));
-                            // 贷款利率
-                            ui.add_sized(egui::vec2(165.0, 21.5),egui::DragValue::new(&mut self.loan.loan_rate));
-                        });
-                    },
-                );
 
+                    self.frequency_ui(ui);
+                    self.dates_ui(ui);
+                    self.fees_ui(ui);
+                    self.rate_changes_ui(ui);
+                    self.prepayments_ui(ui);
 
-                // 中间部分
-                ui.allocate_ui_with_layout(
-                    ui.available_size(),
-                    egui::Layout::left_to_right(egui::Align::Min),
-                    |ui| {
-                        ui.add_space(20.0);
-                        ui.visuals_mut().widgets.inactive.weak_bg_fill = egui::Color32::from_rgb(0,128,255);
-                        ui.visuals_mut().widgets.hovered.weak_bg_fill = egui::Color32::from_rgb(0,130,255);
-                        ui.visuals_mut().widgets.active.weak_bg_fill = PRIMARY_COLOR_ACTIVE;
-                        if ui.add_sized([80.0, 27.0], egui::Button::new(egui::RichText::new("计算").size(14.0))).clicked() {
-                            self.loan.calc();
+                    // 中间部分
+                    ui.allocate_ui_with_layout(
+                        ui.available_size(),
+                        egui::Layout::left_to_right(egui::Align::Min),
+                        |ui| {
+                            ui.add_space(20.0);
+                            ui.visuals_mut().widgets.inactive.weak_bg_fill = egui::Color32::from_rgb(0,128,255);
+                            ui.visuals_mut().widgets.hovered.weak_bg_fill = egui::Color32::from_rgb(0,130,255);
+                            ui.visuals_mut().widgets.active.weak_bg_fill = PRIMARY_COLOR_ACTIVE;
+                            if ui.add_sized([80.0, 27.0], egui::Button::new(egui::RichText::new("计算").size(14.0))).clicked() {
+                                let payment = unit.from_base(self.ui_state.target_payment);
+                                self.ui_state.loan_errors = self.loan.solve(self.ui_state.solve_for, payment).err().unwrap_or_default();
+                            }
+                            ui.add_space(18.0);
+                            ui.visuals_mut().widgets.inactive.weak_bg_fill = egui::Color32::from_rgb(204,0,0);
+                            ui.visuals_mut().widgets.hovered.weak_bg_fill = egui::Color32::from_rgb(255,0,0);
+                            ui.visuals_mut().widgets.active.weak_bg_fill = PRIMARY_COLOR_ACTIVE;
+                            if ui.add_sized([80.0, 27.0], egui::Button::new(egui::RichText::new("重置").size(14.0))).clicked() {
+                                self.loan.reset();
+                                self.ui_state.loan_errors.clear();
+                            }
+                        },
+                    );
+
+                    // 输入错误提示，修改输入后立即重新校验；无解的提示保留到下次计算
+                    if !self.ui_state.loan_errors.is_empty() {
+                        if !self.ui_state.loan_errors.iter().any(|e| matches!(e, loan::LoanError::NoSolution(_))) {
+                            let payment = unit.from_base(self.ui_state.target_payment);
+                            self.ui_state.loan_errors = self.loan.validate_solve(self.ui_state.solve_for, payment).err().unwrap_or_default();
                         }
-                        ui.add_space(18.0);
-                        ui.visuals_mut().widgets.inactive.weak_bg_fill = egui::Color32::from_rgb(204,0,0);
-                        ui.visuals_mut().widgets.hovered.weak_bg_fill = egui::Color32::from_rgb(255,0,0);
-                        ui.visuals_mut().widgets.active.weak_bg_fill = PRIMARY_COLOR_ACTIVE;
-                        if ui.add_sized([80.0, 27.0], egui::Button::new(egui::RichText::new("重置").size(14.0))).clicked() {
-                            self.loan.reset();
+                        for e in &self.ui_state.loan_errors {
+                            ui.colored_label(egui::Color32::LIGHT_RED, e.to_string());
                         }
-                    },
-                );
+                    }
 
-                // 下半部分
-                ui.allocate_ui_with_layout(
-                    ui.available_size(),
-                    egui::Layout::left_to_right(egui::Align::Min),
-                    |ui| {
-                        ui.vertical(|ui| {
-                            // 竖直方向上标签间距
+                    // 下半部分
+                    loan_result_ui(ui, self.loan.result.as_ref(), unit);
+                    self.loan_cost_ui(ui);
 
-                            //ui.spacing_mut().item_spacing = temp_space;
-                            ui.label(egui::RichText::new("月均还款(万元):").size(16.0));
-                            ui.label(egui::RichText::new("利息金额(万元):").size(16.0));
-                            ui.label(egui::RichText::new("贷款金额(万元):").size(16.0));
-                        });
+                    // 还款计划
+                    self.loan_schedule_ui(ui);
+                },
+            );
+        });
+    }
 
-                        ui.vertical(|ui| {
-                            let temp_space = egui::vec2(10.0, 20.0);
-                            ui.spacing_mut().item_spacing = temp_space;
-
-
-                            let money_per_month = &self.loan.money_per_month;
-                            let total_interest = &self.loan.total_interest;
-                            let total_money = &self.loan.total_money;
-                            ui.visuals_mut().widgets.active.bg_fill = egui::Color32::from_rgb(60, 60, 60); // 设置背景颜色
-                            ui.visuals_mut().widgets.hovered.bg_fill = egui::Color32::from_rgb(60, 60, 60); // 设置背景颜色
-                            ui.visuals_mut().widgets.noninteractive.bg_fill = egui::Color32::from_rgb(60, 60, 60); // 设置背景颜色
-                            ui.visuals_mut().widgets.open.bg_fill = egui::Color32::from_rgb(60, 60, 60); // 设置背景颜色
-                            ui.add_sized(
-                                [165.0, 21.5],
-                                egui::Label::new(
-                                    egui::RichText::new(money_per_month)
-                                        .color(egui::Color32::LIGHT_GREEN)
-                                        .size(14.0)
-                                        
-                                )
-                                .wrap(true),
-                            );
-                            ui.add_sized(
-                                [165.0, 21.5],
-                                egui::Label::new(
-                                    egui::RichText::new(total_interest)
-                                        .color(egui::Color32::LIGHT_GREEN)
-                                        .size(14.0),
-                                )
-                                .wrap(true),
-                            );
-                            ui.add_sized(
-                                [165.0, 21.5],
-                                egui::Label::new(
-                                    egui::RichText::new(total_money)
-                                        .color(egui::Color32::LIGHT_GREEN)
-                                        .size(14.0),
-                                )
-                                .wrap(true),
-                            );
-                        });
-                    },
-                );
-            },
-        );
+    fn frequency_ui(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(egui::RichText::new("还款频率与计息方式").size(16.0)).show(ui, |ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(6.0, 6.0);
+            egui::Grid::new("loan_frequency").num_columns(2).show(ui, |ui| {
+                ui.label("还款频率:");
+                ui.add_enabled_ui(self.loan.loan_type != loan::LoanType::BiWeekly, |ui| {
+                    frequency_combo(ui, "payment_frequency", &mut self.loan.payment_frequency, &[
+                        rate::Frequency::Weekly,
+                        rate::Frequency::BiWeekly,
+                        rate::Frequency::Monthly,
+                        rate::Frequency::Quarterly,
+                        rate::Frequency::Annual,
+                    ]);
+                });
+                ui.end_row();
+                ui.label("年利率:");
+                ui.horizontal(|ui| {
+                    for convention in [rate::RateConvention::Nominal, rate::RateConvention::Effective] {
+                        ui.radio_value(&mut self.loan.rate_convention, convention, convention.to_str());
+                    }
+                });
+                ui.end_row();
+                ui.label("计息频率:");
+                ui.add_enabled_ui(self.loan.rate_convention == rate::RateConvention::Nominal, |ui| {
+                    frequency_combo(ui, "compounding", &mut self.loan.compounding, &rate::Frequency::ALL);
+                });
+                ui.end_row();
+            });
+            // 换算后的实际年利率
+            let annual = self.loan.loan_rate + self.loan.rate_spread_bp / 100.0;
+            let effective = match self.loan.rate_convention {
+                rate::RateConvention::Nominal => rate::effective_annual_rate(annual / 100.0, self.loan.compounding) * 100.0,
+                rate::RateConvention::Effective => annual,
+            };
+            ui.label(format!("实际年利率: {:.4}%", effective));
+        });
+    }
+
+    fn dates_ui(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(egui::RichText::new("还款日期").size(16.0)).show(ui, |ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(6.0, 6.0);
+            let mut dated = self.loan.start_date.is_some();
+            if ui.checkbox(&mut dated, "按日期生成还款计划").changed() {
+                self.loan.start_date = dated.then(date::Date::today);
+                self.loan.first_payment_date = None;
+            }
+            let Some(start) = self.loan.start_date.as_mut() else {
+                return;
+            };
+            egui::Grid::new("loan_dates").num_columns(2).show(ui, |ui| {
+                ui.label("起始日期:");
+                date_edit(ui, "start_date", start);
+                ui.end_row();
+
+                ui.label("首次还款日:");
+                ui.horizontal(|ui| {
+                    let mut custom = self.loan.first_payment_date.is_some();
+                    if ui.checkbox(&mut custom, "指定").changed() {
+                        self.loan.first_payment_date = custom.then(|| start.add_months(1));
+                    }
+                    if let Some(first) = self.loan.first_payment_date.as_mut() {
+                        let response = date_edit(ui, "first_payment_date", first);
+                        mark_error(ui, self.ui_state.loan_errors.iter().any(|e| e.field() == loan::LoanField::FirstPaymentDate), &response);
+                    }
+                });
+                ui.end_row();
+
+                ui.label("首期计息:");
+                ui.horizontal(|ui| {
+                    for day_count in [date::DayCount::Periodic, date::DayCount::Actual365, date::DayCount::Thirty360] {
+                        ui.radio_value(&mut self.loan.day_count, day_count, day_count.to_str());
+                    }
+                });
+                ui.end_row();
+            });
+        });
+    }
+
+    // 保存、加载、复制和删除命名的贷款方案.
+    fn scenarios_ui(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(egui::RichText::new("已保存的方案").size(16.0)).show(ui, |ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(6.0, 6.0);
+            ui.horizontal(|ui| {
+                ui.label("名称:");
+                ui.add(egui::TextEdit::singleline(&mut self.ui_state.scenario_name).desired_width(160.0));
+                if ui.button("保存当前输入").clicked() {
+                    self.ui_state.scenario_error = self.scenarios.save(&self.ui_state.scenario_name, &self.loan).err();
+                }
+            });
+            if let Some(e) = &self.ui_state.scenario_error {
+                ui.colored_label(egui::Color32::LIGHT_RED, e.to_string());
+            }
+
+            let (mut loaded, mut duplicated, mut deleted) = (None, None, None);
+            for (i, scenario) in self.scenarios.scenarios.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    let loan = &scenario.loan;
+                    ui.label(egui::RichText::new(&scenario.name).strong());
+                    ui.label(format!("{} {} {} {} 年 {}%", loan.loan_type.to_str(), loan.loan_money, loan.unit.to_str(), loan.loan_year, loan.loan_rate));
+                    if ui.button("加载").clicked() {
+                        loaded = Some(i);
+                    }
+                    if ui.button("复制").clicked() {
+                        duplicated = Some(i);
+                    }
+                    if ui.button("删除").clicked() {
+                        deleted = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = loaded {
+                if let Some(loan) = self.scenarios.load(i) {
+                    self.loan = loan;
+                    self.ui_state.scenario_name = self.scenarios.scenarios[i].name.clone();
+                    self.ui_state.solve_for = loan::SolveFor::Payment;
+                    self.ui_state.loan_errors = self.loan.calc().err().unwrap_or_default();
+                }
+            }
+            if let Some(i) = duplicated {
+                self.scenarios.duplicate(i);
+            }
+            if let Some(i) = deleted {
+                self.scenarios.delete(i);
+            }
+        });
+    }
+
+    fn fees_ui(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(egui::RichText::new("费用").size(16.0)).show(ui, |ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(6.0, 6.0);
+            let response = egui::Grid::new("loan_fees").num_columns(2).show(ui, |ui| {
+                let max_money = self.loan.max_money();
+                let unit = self.loan.unit.to_str().to_owned();
+                let fees = &mut self.loan.fees;
+                for (label, value) in [
+                    ("手续费", &mut fees.origination),
+                    ("每期服务费", &mut fees.service),
+                    ("每期保险费", &mut fees.insurance),
+                ] {
+                    ui.label(format!("{}({}):", label, unit));
+                    ui.add(egui::DragValue::new(value)
+                        .clamp_range(0.0..=max_money)
+                        .speed(0.01)
+                        .max_decimals(4));
+                    ui.end_row();
+                }
+            }).response;
+            mark_error(ui, self.ui_state.loan_errors.iter().any(|e| e.field() == loan::LoanField::Fees), &response);
+        });
+    }
+
+    // 包括费用在内的实际借款成本，与名义利率对照显示.
+    fn loan_cost_ui(&self, ui: &mut egui::Ui) {
+        let Some(result) = &self.loan.result else {
+            return;
+        };
+        let percent = |rate: Option<f64>| rate.map_or("-".to_string(), |rate| format!("{:.3}%", rate));
+        ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 16.0;
+            ui.label(format!("名义年利率: {:.3}%", self.loan.annual_rate_at(1)));
+            ui.label(format!("年化利率(APR): {}", percent(result.apr)));
+            ui.label(format!("实际年利率: {}", percent(result.effective_annual_rate)));
+            ui.label(format!("费用总额: {}", self.loan.unit.format(result.total_fees)));
+        });
+    }
+
+    fn rate_changes_ui(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(egui::RichText::new("浮动利率").size(16.0)).show(ui, |ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(6.0, 6.0);
+            ui.horizontal(|ui| {
+                ui.label("加点(基点):");
+                ui.add(egui::DragValue::new(&mut self.loan.rate_spread_bp)
+                    .clamp_range(-500.0..=500.0)
+                    .speed(1.0)
+                    .max_decimals(0));
+            });
+            let max_month = self.loan.months().max(1);
+            let mut removed = None;
+            for (i, change) in self.loan.rate_changes.iter_mut().enumerate() {
+                let row = ui.horizontal(|ui| {
+                    ui.label("从第");
+                    ui.add(egui::DragValue::new(&mut change.month).clamp_range(1..=max_month));
+                    ui.label("月起利率");
+                    ui.add(egui::DragValue::new(&mut change.rate)
+                        .clamp_range(0.0..=loan::MAX_RATE)
+                        .speed(0.01)
+                        .max_decimals(3)
+                        .suffix(" %"));
+                    if ui.small_button("删除").clicked() {
+                        removed = Some(i);
+                    }
+                });
+                mark_error(ui, self.ui_state.loan_errors.iter().any(|e| e.field() == loan::LoanField::RateChange(i)), &row.response);
+            }
+            if let Some(i) = removed {
+                self.loan.rate_changes.remove(i);
+            }
+            if ui.button("添加利率调整").clicked() {
+                self.loan.rate_changes.push(loan::RateChange{
+                    month: 13.min(max_month),
+                    rate: self.loan.loan_rate,
+                });
+            }
+        });
+    }
+
+    fn prepayments_ui(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(egui::RichText::new("提前还款").size(16.0)).show(ui, |ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(6.0, 6.0);
+            let max_month = self.loan.months().max(1);
+            let max_money = self.loan.max_money();
+            let suffix = format!(" {}", self.loan.unit.to_str());
+            let mut removed = None;
+            for (i, prepayment) in self.loan.prepayments.iter_mut().enumerate() {
+                let row = ui.horizontal(|ui| {
+                    ui.label("第");
+                    ui.add(egui::DragValue::new(&mut prepayment.month).clamp_range(1..=max_month));
+                    ui.label("月");
+                    ui.add(egui::DragValue::new(&mut prepayment.amount)
+                        .clamp_range(0.0..=max_money)
+                        .speed(1.0)
+                        .max_decimals(4)
+                        .suffix(suffix.as_str()));
+                    for strategy in [loan::PrepayStrategy::ShortenTerm, loan::PrepayStrategy::ReducePayment] {
+                        ui.selectable_value(&mut prepayment.strategy, strategy, strategy.to_str());
+                    }
+                    if ui.small_button("删除").clicked() {
+                        removed = Some(i);
+                    }
+                });
+                mark_error(ui, self.ui_state.loan_errors.iter().any(|e| e.field() == loan::LoanField::Prepayment(i)), &row.response);
+            }
+            if let Some(i) = removed {
+                self.loan.prepayments.remove(i);
+            }
+            if ui.button("添加提前还款").clicked() {
+                self.loan.prepayments.push(loan::Prepayment{
+                    month: 12.min(max_month),
+                    amount: self.loan.unit.from_base(10.0 * currency::WAN),
+                    ..Default::default()
+                });
+            }
+        });
+    }
+
+    fn loan_schedule_ui(&mut self, ui: &mut egui::Ui) {
+        if self.loan.schedule.is_empty() {
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new(format!("还款计划({}):", self.loan.unit.base().to_str())).size(16.0));
+            for format in [export::ExportFormat::Csv, export::ExportFormat::Json, export::ExportFormat::Markdown] {
+                if ui.button(format!("导出 {}", format.to_str())).clicked() {
+                    let contents = export::export(&self.loan, format);
+                    self.ui_state.export_error = export::save(format, &contents).err();
+                }
+            }
+        });
+        if let Some(e) = &self.ui_state.export_error {
+            ui.colored_label(egui::Color32::LIGHT_RED, format!("导出失败: {}", e));
+        }
+        schedule_charts(ui, "loan_charts", &self.loan.schedule, self.loan.unit);
+        schedule_table(ui, "loan_schedule", &self.loan.schedule, self.loan.unit);
+    }
+
+    fn savings_ui(&mut self, ui: &mut egui::Ui) {
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(10.0, 12.0);
+            let errors = &self.ui_state.savings_errors;
+            let model = &mut self.savings;
+            egui::Grid::new("savings_inputs").num_columns(2).spacing([20.0, 12.0]).show(ui, |ui| {
+                ui.label(egui::RichText::new("本金(元):").size(16.0));
+                let principal = ui.add(egui::DragValue::new(&mut model.principal)
+                    .clamp_range(0.0..=f64::MAX)
+                    .speed(100.0)
+                    .max_decimals(2));
+                mark_error(ui, errors.iter().any(|e| e.field() == savings::SavingsField::Principal), &principal);
+                ui.end_row();
+
+                ui.label(egui::RichText::new("每期存入(元):").size(16.0));
+                ui.horizontal(|ui| {
+                    let contribution = ui.add(egui::DragValue::new(&mut model.contribution)
+                        .clamp_range(0.0..=f64::MAX)
+                        .speed(10.0)
+                        .max_decimals(2));
+                    mark_error(ui, errors.iter().any(|e| e.field() == savings::SavingsField::Contribution), &contribution);
+                    frequency_combo(ui, "contribution_frequency", &mut model.contribution_frequency, &[
+                        rate::Frequency::Weekly,
+                        rate::Frequency::BiWeekly,
+                        rate::Frequency::Monthly,
+                        rate::Frequency::Quarterly,
+                        rate::Frequency::SemiAnnual,
+                        rate::Frequency::Annual,
+                    ]);
+                    for timing in [savings::ContributionTiming::End, savings::ContributionTiming::Start] {
+                        ui.radio_value(&mut model.timing, timing, timing.to_str());
+                    }
+                });
+                ui.end_row();
+
+                ui.label(egui::RichText::new("年利率(%):").size(16.0));
+                ui.horizontal(|ui| {
+                    let rate = ui.add(egui::DragValue::new(&mut model.rate)
+                        .clamp_range(0.0..=savings::MAX_RATE)
+                        .speed(0.01)
+                        .max_decimals(3));
+                    mark_error(ui, errors.iter().any(|e| e.field() == savings::SavingsField::Rate), &rate);
+                    ui.label("计息:");
+                    frequency_combo(ui, "savings_compounding", &mut model.compounding, &rate::Frequency::ALL);
+                });
+                ui.end_row();
+
+                ui.label(egui::RichText::new("存期(年):").size(16.0));
+                let years = ui.add(egui::DragValue::new(&mut model.years)
+                    .clamp_range(0.0..=savings::MAX_YEAR)
+                    .speed(0.1)
+                    .max_decimals(2));
+                mark_error(ui, errors.iter().any(|e| e.field() == savings::SavingsField::Year), &years);
+                ui.end_row();
+            });
+
+            ui.horizontal(|ui| {
+                if ui.add_sized([80.0, 27.0], egui::Button::new(egui::RichText::new("计算").size(14.0))).clicked() {
+                    self.ui_state.savings_errors = self.savings.calc().err().unwrap_or_default();
+                }
+                if ui.add_sized([80.0, 27.0], egui::Button::new(egui::RichText::new("重置").size(14.0))).clicked() {
+                    self.savings.reset();
+                    self.ui_state.savings_errors.clear();
+                }
+            });
+
+            // 输入错误提示，修改输入后立即重新校验
+            if !self.ui_state.savings_errors.is_empty() {
+                self.ui_state.savings_errors = self.savings.validate().err().unwrap_or_default();
+                for e in &self.ui_state.savings_errors {
+                    ui.colored_label(egui::Color32::LIGHT_RED, e.to_string());
+                }
+            }
+
+            let Some(result) = &self.savings.result else {
+                return;
+            };
+            egui::Grid::new("savings_result").num_columns(2).spacing([20.0, 12.0]).show(ui, |ui| {
+                for (title, value) in [
+                    ("到期总额(元):", result.final_value),
+                    ("累计存入(元):", result.total_contributions),
+                    ("利息收入(元):", result.interest_earned),
+                ] {
+                    ui.label(egui::RichText::new(title).size(16.0));
+                    ui.label(egui::RichText::new(currency::format_money(value)).size(16.0));
+                    ui.end_row();
+                }
+            });
+
+            ui.label(egui::RichText::new("逐年增长(元):").size(16.0));
+            savings_table(ui, &self.savings.growth);
+        });
+    }
+
+    fn tvm_ui(&mut self, ui: &mut egui::Ui) {
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(10.0, 12.0);
+            ui.label("收入为正，支出为负。输入其中四项后点击另一项的“计算”。");
+            egui::Grid::new("tvm_inputs").num_columns(4).spacing([20.0, 12.0]).show(ui, |ui| {
+                for variable in [
+                    tvm::TvmVariable::N,
+                    tvm::TvmVariable::Rate,
+                    tvm::TvmVariable::PresentValue,
+                    tvm::TvmVariable::Payment,
+                    tvm::TvmVariable::FutureValue,
+                ] {
+                    let label = match variable {
+                        tvm::TvmVariable::N => "N(期数):",
+                        tvm::TvmVariable::Rate => "I/Y(年利率 %):",
+                        tvm::TvmVariable::PresentValue => "PV(现值):",
+                        tvm::TvmVariable::Payment => "PMT(每期付款):",
+                        tvm::TvmVariable::FutureValue => "FV(终值):",
+                    };
+                    ui.label(egui::RichText::new(label).size(16.0));
+                    let value = self.tvm.field_mut(variable);
+                    let solved = matches!(self.ui_state.tvm_solved, Some((v, Ok(_))) if v == variable);
+                    let response = ui.add_sized(egui::vec2(165.0, 21.5), egui::DragValue::new(value).speed(1.0).max_decimals(6));
+                    if solved {
+                        ui.painter().rect_stroke(
+                            response.rect.expand(1.0),
+                            egui::Rounding::same(2.0),
+                            egui::Stroke::new(1.5, PRIMARY_COLOR_HOVERED),
+                        );
+                    }
+                    // 金额同时以货币格式显示
+                    match variable {
+                        tvm::TvmVariable::N | tvm::TvmVariable::Rate => ui.label(""),
+                        _ => ui.label(currency::format_money(*value)),
+                    };
+                    if ui.button(format!("计算 {}", variable.to_str())).clicked() {
+                        self.ui_state.tvm_solved = Some((variable, self.tvm.solve(variable)));
+                    }
+                    ui.end_row();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("P/Y:");
+                ui.add(egui::DragValue::new(&mut self.tvm.payments_per_year).clamp_range(1..=365));
+                ui.label("C/Y:");
+                ui.add(egui::DragValue::new(&mut self.tvm.compounding_per_year).clamp_range(1..=365));
+                for timing in [tvm::PaymentTiming::End, tvm::PaymentTiming::Begin] {
+                    ui.radio_value(&mut self.tvm.timing, timing, timing.to_str());
+                }
+            });
+
+            if ui.add_sized([80.0, 27.0], egui::Button::new(egui::RichText::new("重置").size(14.0))).clicked() {
+                self.tvm.reset();
+                self.ui_state.tvm_solved = None;
+            }
+            if let Some((_, Err(e))) = &self.ui_state.tvm_solved {
+                ui.colored_label(egui::Color32::LIGHT_RED, e.to_string());
+            }
+        });
+    }
+
+    fn cash_flow_ui(&mut self, ui: &mut egui::Ui) {
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(10.0, 12.0);
+            let errors = &self.ui_state.cash_flow_errors;
+            let model = &mut self.cash_flows;
+            ui.horizontal(|ui| {
+                for mode in [cashflow::CashFlowMode::Periodic, cashflow::CashFlowMode::Dated] {
+                    ui.radio_value(&mut model.mode, mode, mode.to_str());
+                }
+            });
+            ui.label("流入为正，流出为负。按期时第 0 行为期初，第 t 行为第 t 期末。");
+
+            // 现金流列表
+            let mut remove = None;
+            egui::Grid::new("cash_flow_rows").num_columns(4).spacing([20.0, 8.0]).show(ui, |ui| {
+                for (index, flow) in model.flows.iter_mut().enumerate() {
+                    ui.label(egui::RichText::new(match model.mode {
+                        cashflow::CashFlowMode::Periodic => format!("第 {} 期", index),
+                        cashflow::CashFlowMode::Dated => format!("第 {} 笔", index + 1),
+                    }).size(16.0));
+                    if model.mode == cashflow::CashFlowMode::Dated {
+                        let date = date_edit(ui, &format!("cash_flow_date_{}", index), &mut flow.date);
+                        mark_error(ui, errors.iter().any(|e| e.field() == cashflow::CashFlowField::Date(index)), &date);
+                    }
+                    ui.add(egui::DragValue::new(&mut flow.amount).speed(100.0).max_decimals(2));
+                    if ui.button("删除").clicked() {
+                        remove = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some(index) = remove {
+                model.flows.remove(index);
+            }
+            let add = ui.button("添加一笔");
+            mark_error(ui, errors.iter().any(|e| e.field() == cashflow::CashFlowField::Flows), &add);
+            if add.clicked() {
+                model.push();
+            }
+
+            let unit = model.mode.unit().to_owned();
+            egui::Grid::new("cash_flow_rates").num_columns(2).spacing([20.0, 12.0]).show(ui, |ui| {
+                for (title, value, field) in [
+                    (format!("折现率(%/{}):", unit), &mut model.rate, cashflow::CashFlowField::Rate),
+                    (format!("融资利率(%/{}):", unit), &mut model.finance_rate, cashflow::CashFlowField::FinanceRate),
+                    (format!("再投资利率(%/{}):", unit), &mut model.reinvest_rate, cashflow::CashFlowField::ReinvestRate),
+                ] {
+                    ui.label(egui::RichText::new(title).size(16.0));
+                    let response = ui.add(egui::DragValue::new(value).speed(0.01).max_decimals(3));
+                    mark_error(ui, errors.iter().any(|e| e.field() == field), &response);
+                    ui.end_row();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                if ui.add_sized([80.0, 27.0], egui::Button::new(egui::RichText::new("计算").size(14.0))).clicked() {
+                    self.ui_state.cash_flow_errors = self.cash_flows.calc().err().unwrap_or_default();
+                }
+                if ui.add_sized([80.0, 27.0], egui::Button::new(egui::RichText::new("重置").size(14.0))).clicked() {
+                    self.cash_flows.reset();
+                    self.ui_state.cash_flow_errors.clear();
+                }
+            });
+
+            // 输入错误提示，修改输入后立即重新校验
+            if !self.ui_state.cash_flow_errors.is_empty() {
+                self.ui_state.cash_flow_errors = self.cash_flows.validate().err().unwrap_or_default();
+                for e in &self.ui_state.cash_flow_errors {
+                    ui.colored_label(egui::Color32::LIGHT_RED, e.to_string());
+                }
+            }
+
+            let Some(result) = &self.cash_flows.result else {
+                return;
+            };
+            let unit = self.cash_flows.mode.unit();
+            let percent = |rate: f64| format!("{:.4}%", rate * 100.0);
+            let periods = |value: Option<f64>| value.map_or("无法回收".to_string(), |v| format!("{:.2} {}", v, unit));
+            egui::Grid::new("cash_flow_result").num_columns(2).spacing([20.0, 12.0]).show(ui, |ui| {
+                ui.label(egui::RichText::new("净现值(NPV):").size(16.0));
+                ui.label(egui::RichText::new(currency::format_money(result.npv)).size(16.0));
+                ui.end_row();
+
+                ui.label(egui::RichText::new(format!("内部收益率(IRR/{}):", unit)).size(16.0));
+                ui.label(egui::RichText::new(match result.irr.as_slice() {
+                    [] => "无解".to_string(),
+                    [irr] => percent(*irr),
+                    roots => roots.iter().map(|r| percent(*r)).collect::<Vec<_>>().join("，"),
+                }).size(16.0));
+                ui.end_row();
+
+                ui.label(egui::RichText::new(format!("修正内部收益率(MIRR/{}):", unit)).size(16.0));
+                ui.label(egui::RichText::new(result.mirr.map_or("无解".to_string(), percent)).size(16.0));
+                ui.end_row();
+
+                ui.label(egui::RichText::new("静态回收期:").size(16.0));
+                ui.label(egui::RichText::new(periods(result.payback)).size(16.0));
+                ui.end_row();
+
+                ui.label(egui::RichText::new("动态回收期:").size(16.0));
+                ui.label(egui::RichText::new(periods(result.discounted_payback)).size(16.0));
+                ui.end_row();
+            });
+            if result.irr.len() > 1 {
+                ui.colored_label(egui::Color32::LIGHT_RED, "现金流多次变号，存在多个 IRR，请参考 NPV 或 MIRR");
+            }
+        });
+    }
+
+    fn affordability_ui(&mut self, ui: &mut egui::Ui) {
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(10.0, 12.0);
+            ui.horizontal(|ui| {
+                ui.label("金额单位:");
+                if let Some(unit) = money_unit_ui(ui, "affordability_unit", self.affordability.unit) {
+                    self.affordability.set_unit(unit);
+                }
+            });
+            let unit = self.affordability.unit;
+            let base = unit.base();
+            let errors = &self.ui_state.affordability_errors;
+            let model = &mut self.affordability;
+            egui::Grid::new("affordability_inputs").num_columns(2).spacing([20.0, 12.0]).show(ui, |ui| {
+                for (title, value, speed, field) in [
+                    (format!("月收入({}):", base.to_str()), &mut model.monthly_income, 100.0, affordability::AffordabilityField::Income),
+                    (format!("现有月负债({}):", base.to_str()), &mut model.monthly_debts, 100.0, affordability::AffordabilityField::Debts),
+                    (format!("首付款({}):", unit.to_str()), &mut model.down_payment, 1.0, affordability::AffordabilityField::DownPayment),
+                    ("负债收入比上限(%):".to_string(), &mut model.max_debt_to_income, 0.1, affordability::AffordabilityField::DebtToIncome),
+                    ("最低首付比例(%):".to_string(), &mut model.min_down_payment_ratio, 0.1, affordability::AffordabilityField::DownPaymentRatio),
+                    ("贷款年限(年):".to_string(), &mut model.loan_year, 0.1, affordability::AffordabilityField::Year),
+                    ("年利率(%):".to_string(), &mut model.loan_rate, 0.01, affordability::AffordabilityField::Rate),
+                ] {
+                    ui.label(egui::RichText::new(title).size(16.0));
+                    let response = ui.add(egui::DragValue::new(value)
+                        .clamp_range(0.0..=f64::MAX)
+                        .speed(speed)
+                        .max_decimals(2));
+                    mark_error(ui, errors.iter().any(|e| e.field() == field), &response);
+                    ui.end_row();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                if ui.add_sized([80.0, 27.0], egui::Button::new(egui::RichText::new("计算").size(14.0))).clicked() {
+                    self.ui_state.affordability_errors = self.affordability.calc().err().unwrap_or_default();
+                }
+                if ui.add_sized([80.0, 27.0], egui::Button::new(egui::RichText::new("重置").size(14.0))).clicked() {
+                    self.affordability.reset();
+                    self.ui_state.affordability_errors.clear();
+                }
+            });
+
+            // 输入错误提示，修改输入后立即重新校验
+            if !self.ui_state.affordability_errors.is_empty() {
+                self.ui_state.affordability_errors = self.affordability.validate().err().unwrap_or_default();
+                for e in &self.ui_state.affordability_errors {
+                    ui.colored_label(egui::Color32::LIGHT_RED, e.to_string());
+                }
+            }
+
+            if self.affordability.results.is_empty() {
+                return;
+            }
+            ui.label(egui::RichText::new(format!(
+                "负债收入比允许的最高月供：{}",
+                base.format(self.affordability.max_monthly_payment()),
+            )).size(16.0));
+            egui::Grid::new("affordability_result").num_columns(3).spacing([20.0, 12.0]).striped(true).show(ui, |ui| {
+                ui.label("");
+                for result in &self.affordability.results {
+                    ui.label(egui::RichText::new(result.loan_type.to_str()).size(16.0));
+                }
+                ui.end_row();
+
+                let results = &self.affordability.results;
+                let money = |unit: currency::MoneyUnit, value: fn(&affordability::AffordabilityResult) -> f64| {
+                    results.iter().map(|r| unit.format(value(r))).collect::<Vec<_>>()
+                };
+                for (title, values) in [
+                    ("最高房价:", money(unit, |r| r.max_price)),
+                    ("贷款金额:", money(unit, |r| r.max_loan)),
+                    ("首月月供:", money(base, |r| r.first_payment)),
+                    ("按负债收入比:", money(unit, |r| r.price_by_income)),
+                    ("按首付比例:", money(unit, |r| r.price_by_down_payment)),
+                    ("限制条件:", results.iter().map(|r| r.binding.to_str().to_string()).collect()),
+                ] {
+                    ui.label(egui::RichText::new(title).size(16.0));
+                    for value in values {
+                        ui.label(egui::RichText::new(value).size(16.0));
+                    }
+                    ui.end_row();
+                }
+            });
+        });
     }
 
     fn settings_ui(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
@@ -256,21 +1164,290 @@ impl CalcApp {
             // .auto_shrink([false;2])
             .show(ui, |ui| {
                 ui.label(ui.available_height().to_string());
+                ui.checkbox(&mut self.math_exp.settings.auto_close_parens, "计算时自动补全括号");
+                ui.checkbox(&mut self.math_exp.settings.ieee_semantics, "使用 IEEE 754 语义 (允许 NaN 和 inf)");
+                ui.horizontal(|ui| {
+                    ui.label("三角函数单位:");
+                    ui.radio_value(&mut self.math_exp.settings.angle_mode, math_exp::AngleMode::Radian, "弧度");
+                    ui.radio_value(&mut self.math_exp.settings.angle_mode, math_exp::AngleMode::Degree, "角度");
+                });
                 ctx.settings_ui(ui);
             });
     }
 
     pub(crate) fn new(cc: &eframe::CreationContext<'_>) -> Self {
         calc_config::custom_font(cc);
+        let mut ui_state = calc_state::UiState::default();
+        let text = cc.storage.and_then(|storage| storage.get_string(scenario::STORAGE_KEY));
+        let scenarios = match text.as_deref().map(scenario::Scenarios::from_json) {
+            Some(Ok(scenarios)) => scenarios,
+            Some(Err(e)) => {
+                ui_state.scenario_error = Some(e);
+                ui_state.unreadable_scenarios = text;
+                scenario::Scenarios::default()
+            }
+            None => scenario::Scenarios::default(),
+        };
         CalcApp {
-            ui_state: calc_state::UiState::default(),
+            ui_state,
             math_exp: math_exp::MathExp::default(),
             loan: loan::Loan::default(),
+            combination: loan::CombinationLoan::default(),
+            comparison: loan::Comparison::default(),
+            savings: savings::Savings::default(),
+            tvm: tvm::Tvm::default(),
+            cash_flows: cashflow::CashFlows::default(),
+            affordability: affordability::Affordability::default(),
+            scenarios,
         }
     }
 }
 
+// 剩余本金走势与每期本金/利息构成图，悬停时显示该期的具体数值.
+fn schedule_charts(ui: &mut egui::Ui, id: &str, schedule: &[loan::Payment], unit: currency::MoneyUnit) {
+    if schedule.is_empty() {
+        return;
+    }
+    ui.push_id(id, |ui| {
+        let base = unit.base();
+        ui.label(egui::RichText::new(format!("剩余本金({}):", base.to_str())).size(16.0));
+        let balances: Vec<[f64; 2]> = std::iter::once([0.0, schedule[0].balance + schedule[0].principal + schedule[0].prepayment])
+            .chain(schedule.iter().map(|p| [p.period as f64, p.balance]))
+            .map(|[x, y]| [x, unit.to_base(y)])
+            .collect();
+        let hover = balances.clone();
+        egui::plot::Plot::new("balance")
+            .height(180.0)
+            .allow_scroll(false)
+            .include_y(0.0)
+            .label_formatter(move |_, point| {
+                // 按期数查找悬停位置所在的行，还款计划可能不连续，例如到期还本付息只有最后一期.
+                let [period, balance] = hover.iter().rev()
+                    .find(|[period, _]| *period <= point.x.round())
+                    .copied()
+                    .unwrap_or(hover[0]);
+                format!("第 {} 期\n剩余本金: {}", period, base.format(balance))
+            })
+            .show(ui, |plot_ui| {
+                plot_ui.line(egui::plot::Line::new(balances).color(PRIMARY_COLOR).name("剩余本金"));
+            });
+
+        ui.label(egui::RichText::new(format!("本金与利息构成({}):", base.to_str())).size(16.0));
+        let bars = |value: fn(&loan::Payment) -> f64| -> Vec<egui::plot::Bar> {
+            schedule.iter()
+                .map(|p| egui::plot::Bar::new(p.period as f64, unit.to_base(value(p))).width(1.0))
+                .collect()
+        };
+        let principal = egui::plot::BarChart::new(bars(|p| p.principal + p.prepayment))
+            .color(PRIMARY_COLOR)
+            .name("本金")
+            .element_formatter(Box::new(move |bar, _| {
+                format!("第 {} 期\n本金: {}", bar.argument, base.format(bar.value))
+            }));
+        let interest = egui::plot::BarChart::new(bars(|p| p.interest))
+            .color(Color32::from_rgb(230, 140, 40))
+            .name("利息")
+            .element_formatter(Box::new(move |bar, _| {
+                format!("第 {} 期\n利息: {}", bar.argument, base.format(bar.value))
+            }))
+            .stack_on(&[&principal]);
+        egui::plot::Plot::new("split")
+            .height(180.0)
+            .allow_scroll(false)
+            .include_y(0.0)
+            .legend(egui::plot::Legend::default())
+            .show(ui, |plot_ui| {
+                plot_ui.bar_chart(principal);
+                plot_ui.bar_chart(interest);
+            });
+    });
+}
+
+// 以年、月、日三个输入框编辑日期，日的范围随年月变化.
+fn date_edit(ui: &mut egui::Ui, id: &str, value: &mut date::Date) -> egui::Response {
+    ui.push_id(id, |ui| {
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut value.year).clamp_range(1900..=2200).suffix(" 年"));
+            ui.add(egui::DragValue::new(&mut value.month).clamp_range(1..=12).suffix(" 月"));
+            value.day = value.day.min(date::days_in_month(value.year, value.month));
+            ui.add(egui::DragValue::new(&mut value.day)
+                .clamp_range(1..=date::days_in_month(value.year, value.month))
+                .suffix(" 日"));
+        }).response
+    }).inner
+}
+
+// 从 options 中选择频率的下拉框.
+fn frequency_combo(ui: &mut egui::Ui, id: &str, frequency: &mut rate::Frequency, options: &[rate::Frequency]) {
+    egui::ComboBox::from_id_source(id)
+        .selected_text(frequency.to_str())
+        .show_ui(ui, |ui| {
+            for option in options {
+                ui.selectable_value(frequency, *option, option.to_str());
+            }
+        });
+}
+
+// 以表格显示储蓄每年的增长.
+fn savings_table(ui: &mut egui::Ui, growth: &[savings::SavingsYear]) {
+    use egui_extras::{Column, TableBuilder};
+
+    TableBuilder::new(ui)
+        .striped(true)
+        .max_scroll_height(240.0)
+        .column(Column::auto().at_least(40.0))
+        .columns(Column::auto().at_least(90.0), 3)
+        .header(20.0, |mut header| {
+            for title in ["年份", "当年存入", "当年利息", "年末余额"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(18.0, growth.len(), |index, mut row| {
+                let year = &growth[index];
+                row.col(|ui| {
+                    ui.label(year.year.to_string());
+                });
+                for value in [year.contributions, year.interest, year.balance] {
+                    row.col(|ui| {
+                        ui.label(currency::format_money(value));
+                    });
+                }
+            });
+        });
+}
+
+// 以表格显示还款计划.
+fn schedule_table(ui: &mut egui::Ui, id: &str, schedule: &[loan::Payment], unit: currency::MoneyUnit) {
+    use egui_extras::{Column, TableBuilder};
+
+    // 指定了起始日期时显示还款日期列
+    let dated = schedule.iter().any(|p| p.date.is_some());
+    ui.push_id(id, |ui| {
+        TableBuilder::new(ui)
+            .striped(true)
+            .max_scroll_height(240.0)
+            .column(Column::auto().at_least(40.0))
+            .columns(Column::auto().at_least(80.0), dated as usize)
+            .column(Column::auto().at_least(50.0))
+            .columns(Column::auto().at_least(70.0), 5)
+            .header(20.0, |mut header| {
+                for title in ["期数", "还款日期", "利率(%)", "还款额", "本金", "利息", "提前还款", "剩余本金"] {
+                    if title == "还款日期" && !dated {
+                        continue;
+                    }
+                    header.col(|ui| {
+                        ui.strong(title);
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(18.0, schedule.len(), |index, mut row| {
+                    let payment = &schedule[index];
+                    row.col(|ui| {
+                        ui.label(payment.period.to_string());
+                    });
+                    if dated {
+                        row.col(|ui| {
+                            ui.label(payment.date.map(|d| d.to_string()).unwrap_or_default());
+                        });
+                    }
+                    row.col(|ui| {
+                        ui.label(format!("{:.3}", payment.rate));
+                    });
+                    for value in [payment.payment, payment.principal, payment.interest, payment.prepayment, payment.balance] {
+                        row.col(|ui| {
+                            ui.label(unit.format(value));
+                        });
+                    }
+                });
+            });
+    });
+}
+
+// 金额单位的下拉框，选择了不同的单位时返回新单位.
+fn money_unit_ui(ui: &mut egui::Ui, id: &str, current: currency::MoneyUnit) -> Option<currency::MoneyUnit> {
+    let mut unit = current;
+    egui::ComboBox::from_id_source(id)
+        .selected_text(format!("{} ({})", unit.to_str(), unit.symbol()))
+        .show_ui(ui, |ui| {
+            for option in currency::MoneyUnit::ALL {
+                ui.selectable_value(&mut unit, option, format!("{} ({})", option.to_str(), option.symbol()));
+            }
+        });
+    (unit != current).then_some(unit)
+}
+
+// 显示贷款的汇总结果.
+fn loan_result_ui(ui: &mut egui::Ui, result: Option<&loan::LoanResult>, unit: currency::MoneyUnit) {
+    ui.allocate_ui_with_layout(
+        ui.available_size(),
+        egui::Layout::left_to_right(egui::Align::Min),
+        |ui| {
+            let results = match result {
+                Some(result) => [
+                    result.first_payment,
+                    result.last_payment,
+                    result.average_payment,
+                    result.total_interest,
+                    result.total_paid,
+                    result.interest_saved,
+                ].map(|value| unit.format(value)),
+                None => Default::default(),
+            };
+            ui.vertical(|ui| {
+                // 竖直方向上标签间距
+                ui.spacing_mut().item_spacing = egui::vec2(20.0, 20.0);
+                for title in ["首月还款", "末月还款", "月均还款", "利息总额", "还款总额", "节省利息"] {
+                    ui.label(egui::RichText::new(format!("{}({}):", title, unit.base().to_str())).size(16.0));
+                }
+            });
+
+            ui.vertical(|ui| {
+                let temp_space = egui::vec2(10.0, 20.0);
+                ui.spacing_mut().item_spacing = temp_space;
+
+                ui.visuals_mut().widgets.active.bg_fill = egui::Color32::from_rgb(60, 60, 60); // 设置背景颜色
+                ui.visuals_mut().widgets.hovered.bg_fill = egui::Color32::from_rgb(60, 60, 60); // 设置背景颜色
+                ui.visuals_mut().widgets.noninteractive.bg_fill = egui::Color32::from_rgb(60, 60, 60); // 设置背景颜色
+                ui.visuals_mut().widgets.open.bg_fill = egui::Color32::from_rgb(60, 60, 60); // 设置背景颜色
+                for value in results {
+                    ui.add_sized(
+                        [165.0, 21.5],
+                        egui::Label::new(
+                            egui::RichText::new(value)
+                                .color(egui::Color32::LIGHT_GREEN)
+                                .size(14.0),
+                        )
+                        .wrap(true),
+                    );
+                }
+            });
+        },
+    );
+}
+
+// 为校验失败的输入框绘制红色边框.
+fn mark_error(ui: &mut egui::Ui, invalid: bool, response: &egui::Response) {
+    if invalid {
+        ui.painter().rect_stroke(
+            response.rect.expand(1.0),
+            egui::Rounding::same(2.0),
+            egui::Stroke::new(1.5, egui::Color32::LIGHT_RED),
+        );
+    }
+}
+
 impl eframe::App for CalcApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        if let Some(text) = self.ui_state.unreadable_scenarios.take() {
+            storage.set_string(scenario::BACKUP_KEY, text);
+        }
+        storage.set_string(scenario::STORAGE_KEY, self.scenarios.to_json());
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         egui::CentralPanel::default().show(ctx, |ui| {
             let menu_size = egui::vec2(165.0, ui.available_height());
@@ -285,6 +1462,10 @@ impl eframe::App for CalcApp {
                         for (name, menu, nav) in [
                             ("standard", "标准", calc_state::Nav::Standard),
                             ("loan", "利率", calc_state::Nav::Loan),
+                            ("savings", "储蓄", calc_state::Nav::Savings),
+                            ("tvm", "TVM", calc_state::Nav::Tvm),
+                            ("cashflow", "现金流", calc_state::Nav::CashFlow),
+                            ("affordability", "购房", calc_state::Nav::Affordability),
                             ("settings", "设置", calc_state::Nav::Settings),
                         ] {
                             let menu_size = egui::vec2(120.0, 32.0);
@@ -324,6 +1505,10 @@ impl eframe::App for CalcApp {
                             |ui| match self.ui_state.page {
                                 calc_state::Nav::Standard => self.standard_ui(ctx, ui),
                                 calc_state::Nav::Loan => self.loan_ui(ctx, ui),
+                                calc_state::Nav::Savings => self.savings_ui(ui),
+                                calc_state::Nav::Tvm => self.tvm_ui(ui),
+                                calc_state::Nav::CashFlow => self.cash_flow_ui(ui),
+                                calc_state::Nav::Affordability => self.affordability_ui(ui),
                                 calc_state::Nav::Settings => self.settings_ui(ctx, ui),
                             },
                         );
@@ -1,11 +1,36 @@
 use eframe::egui::*;
 
+use crate::loan;
+use crate::affordability;
+use crate::cashflow;
+use crate::savings;
+use crate::scenario;
+use crate::tvm;
+
 
 #[derive(Default)]
 pub struct UiState{
     pub page: Nav,
     pub at: AnimationTime,
     pub current_menu_line_rect: Option<Rect>,
+    pub export_error: Option<String>,
+    pub loan_errors: Vec<loan::LoanError>,
+    pub loan_view: LoanView,
+    pub solve_for: loan::SolveFor,
+    // 反求时的目标首月还款(元).
+    pub target_payment: f64,
+    pub combination_errors: Vec<(usize, loan::LoanError)>,
+    pub comparison_errors: Vec<(usize, loan::LoanError)>,
+    pub savings_errors: Vec<savings::SavingsError>,
+    pub cash_flow_errors: Vec<cashflow::CashFlowError>,
+    pub affordability_errors: Vec<affordability::AffordabilityError>,
+    // 保存贷款方案时输入的名称.
+    pub scenario_name: String,
+    pub scenario_error: Option<scenario::ScenarioError>,
+    // 启动时无法读取的方案原文，下次保存时备份，不直接覆盖.
+    pub unreadable_scenarios: Option<String>,
+    // 最近一次求解的 TVM 变量及其结果.
+    pub tvm_solved: Option<(tvm::TvmVariable, Result<f64, tvm::TvmError>)>,
 }
 
 
@@ -14,6 +39,10 @@ pub enum Nav{
     #[default]
     Standard,
     Loan,
+    Savings,
+    Tvm,
+    CashFlow,
+    Affordability,
     Settings,
 }
 
@@ -26,6 +55,18 @@ impl Nav {
             Nav::Loan => {
                 "loan"
             },
+            Nav::Savings => {
+                "savings"
+            },
+            Nav::Tvm => {
+                "tvm"
+            },
+            Nav::CashFlow => {
+                "cashflow"
+            },
+            Nav::Affordability => {
+                "affordability"
+            },
             Nav::Settings => {
                 "settings"
             },
@@ -33,6 +74,25 @@ impl Nav {
     }
 }
 
+// 贷款页面的显示内容.
+#[derive(Default,Clone,Copy,Debug,PartialEq)]
+pub enum LoanView{
+    #[default]
+    Single,
+    Combination,
+    Comparison,
+}
+
+impl LoanView {
+    pub fn to_str(&self) -> &str{
+        match self{
+            LoanView::Single => "单一贷款",
+            LoanView::Combination => "组合贷款",
+            LoanView::Comparison => "还款方式对比",
+        }
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct AnimationTime{
     pub menu_change: f32,
@@ -0,0 +1,423 @@
+// 现金流的折现计算.
+// flows[t] 为第 t 期末的现金流，流入为正、流出为负，flows[0] 为期初.
+// 按日期的现金流以 (日期, 金额) 表示，以第一笔现金流的日期为起点，按实际天数/365 计算年数.
+
+use crate::date::{Date, DayCount};
+use crate::solver;
+
+// 每期折现率 rate 下的净现值.
+pub fn npv(rate: f64, flows: &[f64]) -> f64{
+    present_value(rate, &periodic(flows))
+}
+
+// 内部收益率的搜索范围(每期).
+const IRR_MIN: f64 = -0.99;
+const IRR_MAX: f64 = 10.0;
+// 搜索多个根时 [IRR_MIN, 1] 与 [1, IRR_MAX] 的分段数，常见的收益率所在的区间分得更细.
+const IRR_STEPS: (usize, usize) = (400, 90);
+
+// 每期的内部收益率，即使净现值为零的折现率。现金流没有变号或找不到根时返回 None，
+// 有多个根时返回最接近 0 的一个.
+pub fn irr(flows: &[f64]) -> Option<f64>{
+    closest_to_zero(irr_roots(flows))
+}
+
+// 搜索范围内所有的内部收益率，按从小到大排列。现金流多次变号时可能有多个根，也可能没有根.
+pub fn irr_roots(flows: &[f64]) -> Vec<f64>{
+    roots(&periodic(flows))
+}
+
+// 修正内部收益率：流出按融资利率 finance_rate 折现到期初，流入按再投资利率 reinvest_rate
+// 复利到期末，再求使两者相等的每期收益率。没有流入或流出时返回 None.
+pub fn mirr(flows: &[f64], finance_rate: f64, reinvest_rate: f64) -> Option<f64>{
+    modified_irr(&periodic(flows), finance_rate, reinvest_rate)
+}
+
+// 静态回收期(期)：累计现金流由负转为非负的时点，期内按均匀流入插值。无法回收时返回 None.
+pub fn payback_period(flows: &[f64]) -> Option<f64>{
+    payback(&periodic(flows), None)
+}
+
+// 动态回收期(期)：按每期折现率 rate 折现后的累计现金流由负转为非负的时点.
+pub fn discounted_payback_period(rate: f64, flows: &[f64]) -> Option<f64>{
+    payback(&periodic(flows), Some(rate))
+}
+
+// 按日期的现金流在年利率 rate 下的净现值.
+pub fn xnpv(rate: f64, flows: &[(Date, f64)]) -> f64{
+    present_value(rate, &dated(flows))
+}
+
+// 按日期的现金流的所有年化内部收益率，按从小到大排列.
+pub fn xirr_roots(flows: &[(Date, f64)]) -> Vec<f64>{
+    roots(&dated(flows))
+}
+
+// 按日期的现金流的年化内部收益率，有多个根时返回最接近 0 的一个.
+pub fn xirr(flows: &[(Date, f64)]) -> Option<f64>{
+    closest_to_zero(xirr_roots(flows))
+}
+
+// 按日期的现金流的年化修正内部收益率.
+pub fn xmirr(flows: &[(Date, f64)], finance_rate: f64, reinvest_rate: f64) -> Option<f64>{
+    modified_irr(&dated(flows), finance_rate, reinvest_rate)
+}
+
+// 按日期的现金流的静态回收期(年).
+pub fn xpayback_period(flows: &[(Date, f64)]) -> Option<f64>{
+    payback(&dated(flows), None)
+}
+
+// 按日期的现金流在年利率 rate 下的动态回收期(年).
+pub fn xdiscounted_payback_period(rate: f64, flows: &[(Date, f64)]) -> Option<f64>{
+    payback(&dated(flows), Some(rate))
+}
+
+// 以下函数使用 (时点, 金额) 形式的现金流，时点为距第一笔现金流的期数或年数.
+
+fn periodic(flows: &[f64]) -> Vec<(f64, f64)>{
+    flows.iter().enumerate().map(|(t, amount)| (t as f64, *amount)).collect()
+}
+
+fn dated(flows: &[(Date, f64)]) -> Vec<(f64, f64)>{
+    let Some((start, _)) = flows.first() else {
+        return Vec::new();
+    };
+    flows.iter()
+        .map(|(date, amount)| (DayCount::Actual365.year_fraction(*start, *date).unwrap_or(0.0), *amount))
+        .collect()
+}
+
+fn present_value(rate: f64, flows: &[(f64, f64)]) -> f64{
+    flows.iter().map(|(t, amount)| amount / (1.0 + rate).powf(*t)).sum()
+}
+
+fn roots(flows: &[(f64, f64)]) -> Vec<f64>{
+    let has_inflow = flows.iter().any(|(_, a)| *a > 0.0);
+    let has_outflow = flows.iter().any(|(_, a)| *a < 0.0);
+    if !has_inflow || !has_outflow {
+        return Vec::new();
+    }
+    let f = |rate: f64| present_value(rate, flows);
+    let mut result: Vec<f64> = solver::brackets(f, IRR_MIN, 1.0, IRR_STEPS.0)
+        .into_iter()
+        .chain(solver::brackets(f, 1.0, IRR_MAX, IRR_STEPS.1))
+        .filter_map(|(lo, hi)| solver::find_root(f, lo, hi))
+        .collect();
+    // 恰好落在两段交界处的根会被找到两次.
+    result.dedup_by(|a, b| (*a - *b).abs() < 1e-9);
+    result
+}
+
+fn closest_to_zero(roots: Vec<f64>) -> Option<f64>{
+    roots.into_iter().min_by(|a, b| a.abs().total_cmp(&b.abs()))
+}
+
+fn modified_irr(flows: &[(f64, f64)], finance_rate: f64, reinvest_rate: f64) -> Option<f64>{
+    let end = flows.last()?.0;
+    if end <= 0.0 {
+        return None;
+    }
+    let outflows: f64 = flows.iter()
+        .filter(|(_, a)| *a < 0.0)
+        .map(|(t, a)| -a / (1.0 + finance_rate).powf(*t))
+        .sum();
+    let inflows: f64 = flows.iter()
+        .filter(|(_, a)| *a > 0.0)
+        .map(|(t, a)| a * (1.0 + reinvest_rate).powf(end - t))
+        .sum();
+    if outflows <= 0.0 || inflows <= 0.0 {
+        return None;
+    }
+    Some((inflows / outflows).powf(1.0 / end) - 1.0)
+}
+
+fn payback(flows: &[(f64, f64)], rate: Option<f64>) -> Option<f64>{
+    // 没有流出时不需要回收.
+    if flows.iter().all(|(_, a)| *a >= 0.0) {
+        return flows.first().map(|(t, _)| *t);
+    }
+    let mut cumulative = 0.0;
+    let mut previous_time = 0.0;
+    for (t, amount) in flows {
+        let amount = match rate{
+            Some(rate) => amount / (1.0 + rate).powf(*t),
+            None => *amount,
+        };
+        let before = cumulative;
+        cumulative += amount;
+        if before < 0.0 && cumulative >= 0.0 {
+            // 从上一笔现金流到本笔之间均匀回收.
+            return Some(previous_time + (t - previous_time) * -before / amount);
+        }
+        previous_time = *t;
+    }
+    None
+}
+
+// 现金流的时点表示方式.
+#[derive(Default,Clone,Copy,Debug,PartialEq)]
+pub enum CashFlowMode{
+    // 按期：第 t 行为第 t 期末，第 0 行为期初.
+    #[default]
+    Periodic,
+    // 按日期.
+    Dated,
+}
+
+impl CashFlowMode{
+    pub fn to_str(&self) -> &str{
+        match self{
+            CashFlowMode::Periodic => "按期",
+            CashFlowMode::Dated => "按日期",
+        }
+    }
+
+    // 利率与回收期的单位.
+    pub fn unit(&self) -> &str{
+        match self{
+            CashFlowMode::Periodic => "期",
+            CashFlowMode::Dated => "年",
+        }
+    }
+}
+
+// 一笔现金流，按期计算时忽略日期.
+#[derive(Clone,Debug,PartialEq)]
+pub struct CashFlow{
+    pub date: Date,
+    pub amount: f64,
+}
+
+// 现金流分析的结果，利率均为小数形式.
+#[derive(Default,Clone,Debug,PartialEq)]
+pub struct CashFlowResult{
+    pub npv: f64,
+    // 所有内部收益率，为空表示无解，多于一个时 IRR 不能用于判断项目优劣.
+    pub irr: Vec<f64>,
+    pub mirr: Option<f64>,
+    // 静态回收期.
+    pub payback: Option<f64>,
+    // 动态回收期.
+    pub discounted_payback: Option<f64>,
+}
+
+// 现金流输入中出错的字段.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum CashFlowField{
+    Flows,
+    Rate,
+    FinanceRate,
+    ReinvestRate,
+    // 第几笔现金流的日期，从 0 开始.
+    Date(usize),
+}
+
+// 现金流输入的校验错误.
+#[derive(Clone,Debug,PartialEq)]
+pub enum CashFlowError{
+    // 现金流少于两笔.
+    TooFewFlows,
+    // 折现率不大于 -100%.
+    InvalidRate,
+    InvalidFinanceRate,
+    InvalidReinvestRate,
+    // 日期早于上一笔现金流.
+    UnorderedDate(usize),
+}
+
+impl CashFlowError{
+    pub fn field(&self) -> CashFlowField{
+        match self{
+            CashFlowError::TooFewFlows => CashFlowField::Flows,
+            CashFlowError::InvalidRate => CashFlowField::Rate,
+            CashFlowError::InvalidFinanceRate => CashFlowField::FinanceRate,
+            CashFlowError::InvalidReinvestRate => CashFlowField::ReinvestRate,
+            CashFlowError::UnorderedDate(index) => CashFlowField::Date(*index),
+        }
+    }
+}
+
+impl std::fmt::Display for CashFlowError{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result{
+        match self{
+            CashFlowError::TooFewFlows => write!(f, "至少需要两笔现金流"),
+            CashFlowError::InvalidRate => write!(f, "折现率必须大于 -100%"),
+            CashFlowError::InvalidFinanceRate => write!(f, "融资利率必须大于 -100%"),
+            CashFlowError::InvalidReinvestRate => write!(f, "再投资利率必须大于 -100%"),
+            CashFlowError::UnorderedDate(index) => write!(f, "第 {} 笔现金流的日期早于上一笔", index + 1),
+        }
+    }
+}
+
+#[derive(Clone,PartialEq)]
+pub struct CashFlows{
+    pub mode: CashFlowMode,
+    pub flows: Vec<CashFlow>,
+    // 折现率(%)，按期时为每期利率，按日期时为年利率.
+    pub rate: f64,
+    // MIRR 的融资利率(%).
+    pub finance_rate: f64,
+    // MIRR 的再投资利率(%).
+    pub reinvest_rate: f64,
+    pub result: Option<CashFlowResult>,
+}
+
+impl Default for CashFlows{
+    fn default() -> Self{
+        Self::new()
+    }
+}
+
+impl CashFlows{
+    pub fn new() -> Self{
+        let today = Date::today();
+        Self{
+            mode: CashFlowMode::Periodic,
+            flows: (0..2).map(|year| CashFlow{ date: today.add_months(12 * year), amount: 0.0 }).collect(),
+            rate: 0.0,
+            finance_rate: 0.0,
+            reinvest_rate: 0.0,
+            result: None,
+        }
+    }
+
+    // 在末尾添加一笔现金流，日期为上一笔之后一年.
+    pub fn push(&mut self){
+        let date = self.flows.last().map_or_else(Date::today, |flow| flow.date.add_months(12));
+        self.flows.push(CashFlow{ date, amount: 0.0 });
+    }
+
+    // (时点, 金额) 形式的现金流.
+    fn timed(&self) -> Vec<(f64, f64)>{
+        match self.mode{
+            CashFlowMode::Periodic => periodic(&self.amounts()),
+            CashFlowMode::Dated => dated(&self.flows.iter().map(|flow| (flow.date, flow.amount)).collect::<Vec<_>>()),
+        }
+    }
+
+    pub fn amounts(&self) -> Vec<f64>{
+        self.flows.iter().map(|flow| flow.amount).collect()
+    }
+
+    // 校验输入，返回所有出错字段的错误.
+    pub fn validate(&self) -> Result<(), Vec<CashFlowError>>{
+        let mut errors = Vec::new();
+        if self.flows.len() < 2 {
+            errors.push(CashFlowError::TooFewFlows);
+        }
+        if self.rate <= -100.0 {
+            errors.push(CashFlowError::InvalidRate);
+        }
+        if self.finance_rate <= -100.0 {
+            errors.push(CashFlowError::InvalidFinanceRate);
+        }
+        if self.reinvest_rate <= -100.0 {
+            errors.push(CashFlowError::InvalidReinvestRate);
+        }
+        if self.mode == CashFlowMode::Dated {
+            for (index, pair) in self.flows.windows(2).enumerate() {
+                if pair[1].date < pair[0].date {
+                    errors.push(CashFlowError::UnorderedDate(index + 1));
+                }
+            }
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    // 计算各项指标。输入无效时清空结果并返回错误.
+    pub fn calc(&mut self) -> Result<(), Vec<CashFlowError>>{
+        self.result = None;
+        self.validate()?;
+
+        let flows = self.timed();
+        let rate = self.rate / 100.0;
+        self.result = Some(CashFlowResult{
+            npv: present_value(rate, &flows),
+            irr: roots(&flows),
+            mirr: modified_irr(&flows, self.finance_rate / 100.0, self.reinvest_rate / 100.0),
+            payback: payback(&flows, None),
+            discounted_payback: payback(&flows, Some(rate)),
+        });
+        Ok(())
+    }
+
+    pub fn reset(&mut self){
+        *self = Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64){
+        assert!((actual - expected).abs() < tolerance, "{} != {}", actual, expected);
+    }
+
+    // Excel XIRR 文档中的示例.
+    fn dated_flows() -> Vec<(Date, f64)>{
+        [("2008-01-01", -10000.0), ("2008-03-01", 2750.0), ("2008-10-30", 4250.0), ("2009-02-15", 3250.0), ("2009-04-01", 2750.0)]
+            .into_iter()
+            .map(|(date, amount)| (Date::parse(date).unwrap(), amount))
+            .collect()
+    }
+
+    #[test]
+    fn net_present_value(){
+        assert_close(npv(0.1, &[-1000.0, 300.0, 400.0, 500.0]), -21.04, 0.005);
+        assert_close(npv(0.0, &[-1000.0, 300.0, 400.0, 500.0]), 200.0, 1e-9);
+        assert_eq!(npv(0.1, &[]), 0.0);
+        assert_close(xnpv(0.09, &dated_flows()), 2086.65, 0.005);
+    }
+
+    #[test]
+    fn internal_rates_of_return(){
+        assert_close(irr(&[-1000.0, 300.0, 400.0, 500.0]).unwrap(), 0.08896, 1e-5);
+        // 现金流两次变号，有两个根.
+        let found = irr_roots(&[-100.0, 230.0, -132.0]);
+        assert_eq!(found.len(), 2);
+        assert_close(found[0], 0.10, 1e-9);
+        assert_close(found[1], 0.20, 1e-9);
+        assert_close(irr(&[-100.0, 230.0, -132.0]).unwrap(), 0.10, 1e-9);
+        // 根落在两段搜索范围的交界处时只计一次.
+        let found = irr_roots(&[-100.0, 200.0]);
+        assert_eq!(found.len(), 1);
+        assert_close(found[0], 1.0, 1e-9);
+        // 只有流入或只有流出时没有根.
+        assert!(irr_roots(&[100.0, 200.0]).is_empty());
+        assert_eq!(irr(&[-100.0, -200.0]), None);
+        assert_close(xirr(&dated_flows()).unwrap(), 0.373362535, 1e-8);
+    }
+
+    #[test]
+    fn modified_internal_rate_of_return(){
+        // Excel MIRR 文档中的示例.
+        let flows = [-120000.0, 39000.0, 30000.0, 21000.0, 37000.0, 46000.0];
+        assert_close(mirr(&flows, 0.10, 0.12).unwrap(), 0.126094, 1e-6);
+        assert_close(mirr(&flows, 0.10, 0.14).unwrap(), 0.134759, 1e-6);
+        // 没有流入、没有流出或只有期初的现金流.
+        assert_eq!(mirr(&[-100.0, -50.0], 0.1, 0.1), None);
+        assert_eq!(mirr(&[100.0, 50.0], 0.1, 0.1), None);
+        assert_eq!(mirr(&[-100.0], 0.1, 0.1), None);
+    }
+
+    #[test]
+    fn payback_periods(){
+        assert_close(payback_period(&[-1000.0, 300.0, 400.0, 500.0]).unwrap(), 2.6, 1e-9);
+        // 第 1 期末恰好回收.
+        assert_close(payback_period(&[-100.0, 100.0, 50.0]).unwrap(), 1.0, 1e-9);
+        assert_eq!(payback_period(&[-1000.0, 300.0, 300.0]), None);
+        // 没有流出时不需要回收.
+        assert_eq!(payback_period(&[100.0, 200.0]), Some(0.0));
+        // 折现后第 3 期内回收：折现累计 -132.23，第 3 期折现流入 375.66.
+        assert_close(discounted_payback_period(0.1, &[-1000.0, 500.0, 500.0, 500.0]).unwrap(), 2.352, 1e-3);
+        assert_eq!(discounted_payback_period(0.1, &[-1000.0, 300.0, 400.0, 500.0]), None);
+        // 按日期的现金流以年为单位：累计现金流在第 3 笔(约 0.83 年)之后、第 4 笔之前转正.
+        let years = xpayback_period(&dated_flows()).unwrap();
+        let (start, third, fourth) = (dated_flows()[0].0, dated_flows()[2].0, dated_flows()[3].0);
+        let year = |date: Date| (date.days() - start.days()) as f64 / 365.0;
+        assert!(year(third) < years && years <= year(fourth), "{}", years);
+        assert!(xdiscounted_payback_period(0.09, &dated_flows()).unwrap() > years);
+    }
+}
@@ -0,0 +1,187 @@
+// 金额的显示格式.
+
+// 一万元.
+pub const WAN: f64 = 10_000.0;
+
+// 将金额四舍五入到分，并添加千位分隔符，例如 1234567.891 -> "1,234,567.89".
+pub fn format_money(value: f64) -> String {
+    format_amount(value, 2)
+}
+
+// 将金额四舍五入到 decimals 位小数，并添加千位分隔符.
+pub fn format_amount(value: f64, decimals: usize) -> String {
+    let scale = 10f64.powi(decimals as i32);
+    let units = (value * scale).round();
+    let sign = if units < 0.0 { "-" } else { "" };
+    let units = units.abs();
+    let integer = (units / scale).trunc() as u64;
+    let fraction = (units % scale) as u64;
+
+    let digits = integer.to_string();
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    if decimals == 0 {
+        format!("{}{}", sign, grouped)
+    } else {
+        format!("{}{}.{:0width$}", sign, grouped, fraction, width = decimals)
+    }
+}
+
+// 输入和显示金额使用的单位。不同货币之间不做汇率换算，只改变符号和小数位数.
+#[derive(Default,Clone,Copy,Debug,PartialEq)]
+pub enum MoneyUnit {
+    Yuan,
+    #[default]
+    Wan,
+    Dollar,
+    Euro,
+    Pound,
+    Yen,
+    HongKongDollar,
+}
+
+impl MoneyUnit {
+    pub const ALL: [MoneyUnit; 7] = [
+        MoneyUnit::Yuan,
+        MoneyUnit::Wan,
+        MoneyUnit::Dollar,
+        MoneyUnit::Euro,
+        MoneyUnit::Pound,
+        MoneyUnit::Yen,
+        MoneyUnit::HongKongDollar,
+    ];
+
+    pub fn to_str(&self) -> &str {
+        match self {
+            MoneyUnit::Yuan => "元",
+            MoneyUnit::Wan => "万元",
+            MoneyUnit::Dollar => "美元",
+            MoneyUnit::Euro => "欧元",
+            MoneyUnit::Pound => "英镑",
+            MoneyUnit::Yen => "日元",
+            MoneyUnit::HongKongDollar => "港元",
+        }
+    }
+
+    // 保存和导出时使用的标识.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MoneyUnit::Yuan => "Yuan",
+            MoneyUnit::Wan => "Wan",
+            MoneyUnit::Dollar => "Dollar",
+            MoneyUnit::Euro => "Euro",
+            MoneyUnit::Pound => "Pound",
+            MoneyUnit::Yen => "Yen",
+            MoneyUnit::HongKongDollar => "HongKongDollar",
+        }
+    }
+
+    pub fn symbol(&self) -> &str {
+        match self {
+            MoneyUnit::Yuan | MoneyUnit::Wan => "¥",
+            MoneyUnit::Dollar => "$",
+            MoneyUnit::Euro => "€",
+            MoneyUnit::Pound => "£",
+            MoneyUnit::Yen => "JP¥",
+            MoneyUnit::HongKongDollar => "HK$",
+        }
+    }
+
+    // 显示金额时的小数位数.
+    pub fn decimals(&self) -> usize {
+        match self {
+            MoneyUnit::Yen => 0,
+            _ => 2,
+        }
+    }
+
+    // 以该单位表示金额时精确到最小货币单位所需的小数位数，例如万元精确到分需要 6 位.
+    pub fn precision(&self) -> usize {
+        self.decimals() + self.scale().log10().round() as usize
+    }
+
+    // 同一货币的基本单位，例如万元的基本单位为元.
+    pub fn base(&self) -> MoneyUnit {
+        match self {
+            MoneyUnit::Wan => MoneyUnit::Yuan,
+            unit => *unit,
+        }
+    }
+
+    // 一个该单位等于多少个基本单位.
+    pub fn scale(&self) -> f64 {
+        match self {
+            MoneyUnit::Wan => WAN,
+            _ => 1.0,
+        }
+    }
+
+    pub fn to_base(&self, value: f64) -> f64 {
+        value * self.scale()
+    }
+
+    pub fn from_base(&self, value: f64) -> f64 {
+        value / self.scale()
+    }
+
+    // 切换到 unit 时已输入金额需要乘的倍数。同一货币内按单位换算，例如万元到元为 10000；
+    // 不同货币之间没有汇率，单位只是显示标签，金额保持不变.
+    pub fn conversion_to(&self, unit: MoneyUnit) -> f64 {
+        if self.base() == unit.base() { self.scale() / unit.scale() } else { 1.0 }
+    }
+
+    // 将以该单位计的金额换算为基本单位后格式化，例如 1.5 万元 -> "¥15,000.00".
+    pub fn format(&self, value: f64) -> String {
+        let amount = format_amount(self.to_base(value), self.decimals());
+        match amount.strip_prefix('-') {
+            Some(amount) => format!("-{}{}", self.symbol(), amount),
+            None => format!("{}{}", self.symbol(), amount),
+        }
+    }
+}
+
+impl std::str::FromStr for MoneyUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.iter().copied().find(|v| v.as_str() == s).ok_or_else(|| format!("未知的金额单位: {}", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grouping() {
+        assert_eq!(format_money(0.0), "0.00");
+        assert_eq!(format_money(999.994), "999.99");
+        assert_eq!(format_money(999.995), "1,000.00");
+        assert_eq!(format_money(1234567.891), "1,234,567.89");
+        assert_eq!(format_amount(123456789.0, 0), "123,456,789");
+        assert_eq!(format_amount(12345.6789, 3), "12,345.679");
+    }
+
+    #[test]
+    fn negatives() {
+        assert_eq!(format_money(-1234.5), "-1,234.50");
+        assert_eq!(format_amount(-999999.5, 0), "-1,000,000");
+        // 四舍五入为 0 时不显示负号.
+        assert_eq!(format_money(-0.001), "0.00");
+        assert_eq!(MoneyUnit::Dollar.format(-1234.5), "-$1,234.50");
+    }
+
+    #[test]
+    fn unit_decimals() {
+        assert_eq!(MoneyUnit::Wan.format(1.5), "¥15,000.00");
+        assert_eq!(MoneyUnit::Yen.format(1234567.4), "JP¥1,234,567");
+        assert_eq!(MoneyUnit::HongKongDollar.format(0.125), "HK$0.13");
+        assert_eq!(MoneyUnit::Wan.precision(), 6);
+        assert_eq!(MoneyUnit::Yen.precision(), 0);
+    }
+}
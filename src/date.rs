@@ -0,0 +1,232 @@
+// 公历日期及还款日期的计算.
+
+use std::fmt;
+
+#[derive(Clone,Copy,Debug,PartialEq,Eq,PartialOrd,Ord)]
+pub struct Date{
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date{
+    // 日期无效时返回 None.
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Self>{
+        let valid = (1..=12).contains(&month) && day >= 1 && day <= days_in_month(year, month);
+        valid.then_some(Self{ year, month, day })
+    }
+
+    // 当天的日期，本地版本使用 UTC 日期.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn today() -> Self{
+        let seconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Self::from_days((seconds / 86_400) as i64)
+    }
+
+    // 当天的日期，使用浏览器的本地时间.
+    #[cfg(target_arch = "wasm32")]
+    pub fn today() -> Self{
+        let now = js_sys::Date::new_0();
+        Self{
+            year: now.get_full_year() as i32,
+            month: now.get_month() + 1,
+            day: now.get_date(),
+        }
+    }
+
+    // 自 1970-01-01 起的天数.
+    pub fn days(&self) -> i64{
+        let y = self.year as i64 - if self.month <= 2 { 1 } else { 0 };
+        let era = y.div_euclid(400);
+        let year_of_era = y - era * 400;
+        let month = self.month as i64;
+        let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    // days 的逆运算.
+    pub fn from_days(days: i64) -> Self{
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z - era * 146_097;
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (year_of_era + era * 400 + if month <= 2 { 1 } else { 0 }) as i32;
+        Self{ year, month, day }
+    }
+
+    // 解析 "YYYY-MM-DD" 格式的日期，格式或日期无效时返回 None.
+    pub fn parse(text: &str) -> Option<Self>{
+        let mut parts = text.trim().splitn(3, '-');
+        let year = parts.next()?.parse().ok()?;
+        let month = parts.next()?.parse().ok()?;
+        let day = parts.next()?.parse().ok()?;
+        Self::new(year, month, day)
+    }
+
+    pub fn add_days(&self, days: i64) -> Self{
+        Self::from_days(self.days() + days)
+    }
+
+    // 加上 months 个月。目标月份没有该日时取月末；原日期为月末时结果也为月末，
+    // 例如 1 月 31 日加 1 个月为 2 月 28 日(或 29 日)，4 月 30 日加 1 个月为 5 月 31 日.
+    pub fn add_months(&self, months: i32) -> Self{
+        let total = self.year * 12 + self.month as i32 - 1 + months;
+        let year = total.div_euclid(12);
+        let month = total.rem_euclid(12) as u32 + 1;
+        let last_day = days_in_month(year, month);
+        let day = if self.is_month_end() { last_day } else { self.day.min(last_day) };
+        Self{ year, month, day }
+    }
+
+    pub fn is_month_end(&self) -> bool{
+        self.day == days_in_month(self.year, self.month)
+    }
+}
+
+impl fmt::Display for Date{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+pub fn is_leap_year(year: i32) -> bool{
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+pub fn days_in_month(year: i32, month: u32) -> u32{
+    match month{
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// 计息天数的计算方式.
+#[derive(Default,Clone,Copy,Debug,PartialEq)]
+pub enum DayCount{
+    // 不按天计息，首期与其他各期一样按整期计息.
+    #[default]
+    Periodic,
+    // 实际天数/365.
+    Actual365,
+    // 30/360：每月按 30 天、每年按 360 天计算.
+    Thirty360,
+}
+
+impl DayCount{
+    pub const ALL: [DayCount; 3] = [DayCount::Periodic, DayCount::Actual365, DayCount::Thirty360];
+
+    pub fn to_str(&self) -> &str{
+        match self{
+            DayCount::Periodic => "按整期",
+            DayCount::Actual365 => "实际天数/365",
+            DayCount::Thirty360 => "30/360",
+        }
+    }
+
+    // 保存和导出时使用的标识.
+    pub fn as_str(&self) -> &'static str{
+        match self{
+            DayCount::Periodic => "Periodic",
+            DayCount::Actual365 => "Actual365",
+            DayCount::Thirty360 => "Thirty360",
+        }
+    }
+
+    // from 到 to 之间的计息年数，按整期计息时返回 None.
+    pub fn year_fraction(&self, from: Date, to: Date) -> Option<f64>{
+        match self{
+            DayCount::Periodic => None,
+            DayCount::Actual365 => Some((to.days() - from.days()) as f64 / 365.0),
+            DayCount::Thirty360 => {
+                let d1 = from.day.min(30) as i64;
+                let d2 = if d1 == 30 { to.day.min(30) } else { to.day } as i64;
+                let days = 360 * (to.year - from.year) as i64
+                    + 30 * (to.month as i64 - from.month as i64)
+                    + d2 - d1;
+                Some(days as f64 / 360.0)
+            },
+        }
+    }
+}
+
+impl std::str::FromStr for DayCount{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>{
+        Self::ALL.iter().copied().find(|v| v.as_str() == s).ok_or_else(|| format!("未知的计息方式: {}", s))
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn date(text: &str) -> Date{
+        Date::parse(text).unwrap()
+    }
+
+    #[test]
+    fn add_months(){
+        for (from, months, to) in [
+            ("2023-01-28", 1, "2023-02-28"),
+            ("2023-01-28", 2, "2023-03-28"),
+            ("2023-01-31", 1, "2023-02-28"),
+            ("2024-01-31", 1, "2024-02-29"),
+            ("2023-01-31", 3, "2023-04-30"),
+            ("2023-04-30", 1, "2023-05-31"),
+            ("2023-02-28", 1, "2023-03-31"),
+            ("2024-02-28", 1, "2024-03-28"),
+            ("2023-11-15", 3, "2024-02-15"),
+            ("2023-03-15", -3, "2022-12-15"),
+            ("2023-05-31", -3, "2023-02-28"),
+        ] {
+            assert_eq!(date(from).add_months(months), date(to), "{} + {}", from, months);
+        }
+    }
+
+    #[test]
+    fn days_round_trip(){
+        assert_eq!(date("1970-01-01").days(), 0);
+        assert_eq!(date("2000-03-01").days(), 11_017);
+        assert_eq!(date("1969-12-31").days(), -1);
+        for days in (-800_000..800_000).step_by(997) {
+            assert_eq!(Date::from_days(days).days(), days);
+        }
+        // 跨越闰日.
+        assert_eq!(date("2024-02-28").add_days(1), date("2024-02-29"));
+        assert_eq!(date("2023-02-28").add_days(1), date("2023-03-01"));
+        assert_eq!(date("2100-02-28").add_days(1), date("2100-03-01"));
+    }
+
+    #[test]
+    fn parse(){
+        assert_eq!(Date::parse(" 2023-1-5 "), Date::new(2023, 1, 5));
+        assert_eq!(Date::parse("2023-02-29"), None);
+        assert_eq!(Date::parse("2023-13-01"), None);
+        assert_eq!(Date::parse("2023/01/01"), None);
+        assert_eq!(date("2023-01-05").to_string(), "2023-01-05");
+    }
+
+    #[test]
+    fn year_fraction(){
+        assert_eq!(DayCount::Periodic.year_fraction(date("2023-01-01"), date("2023-02-01")), None);
+        assert_eq!(DayCount::Actual365.year_fraction(date("2023-01-01"), date("2024-01-01")), Some(1.0));
+        assert_eq!(DayCount::Actual365.year_fraction(date("2024-01-01"), date("2025-01-01")), Some(366.0 / 365.0));
+        assert_eq!(DayCount::Actual365.year_fraction(date("2023-01-15"), date("2023-02-15")), Some(31.0 / 365.0));
+        // 30/360：31 日按 30 日计，起始日为 30 日或 31 日时结束日的 31 日也按 30 日计.
+        assert_eq!(DayCount::Thirty360.year_fraction(date("2023-01-15"), date("2023-02-15")), Some(30.0 / 360.0));
+        assert_eq!(DayCount::Thirty360.year_fraction(date("2023-01-31"), date("2023-03-31")), Some(60.0 / 360.0));
+        assert_eq!(DayCount::Thirty360.year_fraction(date("2023-01-15"), date("2023-03-31")), Some(76.0 / 360.0));
+        assert_eq!(DayCount::Thirty360.year_fraction(date("2023-02-28"), date("2023-03-31")), Some(33.0 / 360.0));
+        assert_eq!(DayCount::Thirty360.year_fraction(date("2023-01-01"), date("2024-01-01")), Some(1.0));
+    }
+}
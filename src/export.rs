@@ -0,0 +1,408 @@
+use crate::date::Date;
+use crate::loan;
+
+// 还款计划的导出格式.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub fn to_str(&self) -> &str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Markdown => "Markdown",
+        }
+    }
+
+    pub fn extension(&self) -> &str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+        }
+    }
+
+    pub fn mime_type(&self) -> &str {
+        match self {
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::Json => "application/json",
+            ExportFormat::Markdown => "text/markdown",
+        }
+    }
+}
+
+// 还款计划的表头，金额以贷款的单位计.
+fn schedule_header(loan: &loan::Loan) -> Vec<String> {
+    let unit = loan.unit.to_str();
+    ["期数", "还款日期", "利率(%)"].into_iter().map(String::from)
+        .chain(["还款额", "本金", "利息", "提前还款", "剩余本金"].into_iter().map(|title| format!("{}({})", title, unit)))
+        .collect()
+}
+
+// 导出的利率(%)保留的小数位数.
+const RATE_DECIMALS: usize = 4;
+
+// 以贷款的单位表示的金额，精确到最小货币单位，不加千位分隔符，便于粘贴到电子表格中计算.
+fn money(loan: &loan::Loan, value: f64) -> String {
+    format!("{:.*}", loan.unit.precision(), value)
+}
+
+fn rate(value: f64) -> String {
+    format!("{:.*}", RATE_DECIMALS, value)
+}
+
+// 贷款的输入参数及汇总结果，按导出时的顺序排列.
+fn parameters(loan: &loan::Loan) -> Vec<(String, String)> {
+    // 带金额单位的参数名.
+    let label = |name: &str| format!("{}({})", name, loan.unit.to_str());
+    let money = |value: f64| money(loan, value);
+    [
+        ("还款方式".to_string(), loan.loan_type.to_str().to_string()),
+        ("金额单位".to_string(), format!("{} ({})", loan.unit.to_str(), loan.unit.symbol())),
+        ("贷款年限(年)".to_string(), loan.loan_year.to_string()),
+        (label("贷款金额"), money(loan.loan_money)),
+        ("贷款利率(%)".to_string(), rate(loan.loan_rate)),
+        ("加点(基点)".to_string(), loan.rate_spread_bp.to_string()),
+        ("还款频率".to_string(), loan.payment_frequency.to_str().to_string()),
+        ("利率类型".to_string(), loan.rate_convention.to_str().to_string()),
+        ("计息频率".to_string(), loan.compounding.to_str().to_string()),
+    ].into_iter().chain(match loan.loan_type {
+        loan::LoanType::GraduatedGeometric => Some(("每年增幅(%)".to_string(), rate(loan.graduation))),
+        loan::LoanType::GraduatedArithmetic => Some((label("每年增额"), money(loan.graduation))),
+        _ => None,
+    }).chain(loan.start_date.into_iter().flat_map(|start| [
+        ("起始日期".to_string(), start.to_string()),
+        ("首次还款日".to_string(), loan.payment_date(1).map(|d| d.to_string()).unwrap_or_default()),
+        ("首期计息".to_string(), loan.day_count.to_str().to_string()),
+    ])).chain([
+        (label("手续费"), money(loan.fees.origination)),
+        (label("每期服务费"), money(loan.fees.service)),
+        (label("每期保险费"), money(loan.fees.insurance)),
+    ]).chain(loan.rate_changes.iter().map(|c| (
+        "利率调整".to_string(),
+        format!("第 {} 月起 {}%", c.month, rate(c.rate)),
+    ))).chain(loan.prepayments.iter().map(|p| (
+        "提前还款".to_string(),
+        format!("第 {} 月 {} {} {}", p.month, money(p.amount), loan.unit.to_str(), p.strategy.to_str()),
+    ))).chain(loan.result.iter().flat_map(|result| [
+        (label("首月还款"), money(result.first_payment)),
+        (label("末月还款"), money(result.last_payment)),
+        (label("月均还款"), money(result.average_payment)),
+        (label("利息总额"), money(result.total_interest)),
+        (label("还款总额"), money(result.total_paid)),
+        (label("节省利息"), money(result.interest_saved)),
+        (label("费用总额"), money(result.total_fees)),
+        ("年化利率 APR(%)".to_string(), result.apr.map(rate).unwrap_or_default()),
+        ("实际年利率(%)".to_string(), result.effective_annual_rate.map(rate).unwrap_or_default()),
+    ])).collect()
+}
+
+// 还款计划中一期的各列，与 schedule_header 对应.
+fn schedule_row(loan: &loan::Loan, p: &loan::Payment) -> Vec<String> {
+    [p.period.to_string(), date(p), rate(p.rate)].into_iter()
+        .chain([p.payment, p.principal, p.interest, p.prepayment, p.balance].map(|value| money(loan, value)))
+        .collect()
+}
+
+// 将贷款参数和还款计划转换为指定格式的文本.
+pub fn export(loan: &loan::Loan, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Csv => to_csv(loan),
+        ExportFormat::Json => to_json(loan),
+        ExportFormat::Markdown => to_markdown(loan),
+    }
+}
+
+// 导出文件的默认文件名.
+pub fn file_name(format: ExportFormat) -> String {
+    format!("还款计划.{}", format.extension())
+}
+
+// 还款日期，没有日期时为空.
+fn date(payment: &loan::Payment) -> String {
+    payment.date.map(|d| d.to_string()).unwrap_or_default()
+}
+
+// CSV 以 UTF-8 BOM 开头，Excel 才能正确识别中文.
+fn to_csv(loan: &loan::Loan) -> String {
+    let mut out = String::from("\u{feff}");
+    let mut push_row = |fields: &[String]| {
+        let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        out.push_str(&fields.join(","));
+        out.push_str("\r\n");
+    };
+    for (name, value) in parameters(loan) {
+        push_row(&[name, value]);
+    }
+    push_row(&[]);
+    push_row(&schedule_header(loan));
+    for p in &loan.schedule {
+        push_row(&schedule_row(loan, p));
+    }
+    out
+}
+
+// 含有逗号、引号或换行的字段用引号括起来，字段中的引号写两次.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn to_json(loan: &loan::Loan) -> String {
+    let schedule: Vec<serde_json::Value> = loan.schedule.iter().map(|p| serde_json::json!({
+        "period": p.period,
+        "date": p.date.map(|d| d.to_string()),
+        "rate": p.rate,
+        "payment": p.payment,
+        "principal": p.principal,
+        "interest": p.interest,
+        "prepayment": p.prepayment,
+        "balance": p.balance,
+    })).collect();
+    let mut value = loan_to_json(loan);
+    value["currency_symbol"] = loan.unit.symbol().into();
+    // 导出实际的首次还款日，未指定时由起始日期推算.
+    value["first_payment_date"] = loan.payment_date(1).map(|d| d.to_string()).into();
+    value["result"] = loan.result.as_ref().map(|result| serde_json::json!({
+        "first_payment": result.first_payment,
+        "last_payment": result.last_payment,
+        "average_payment": result.average_payment,
+        "total_interest": result.total_interest,
+        "total_paid": result.total_paid,
+        "interest_saved": result.interest_saved,
+        "total_fees": result.total_fees,
+        "apr": result.apr,
+        "effective_annual_rate": result.effective_annual_rate,
+    })).into();
+    value["schedule"] = schedule.into();
+    serde_json::to_string_pretty(&value).unwrap_or_default()
+}
+
+// 贷款的输入参数，导出的 JSON 与保存的方案共用.
+// 枚举保存为 as_str 的标识，例如 "EqualInterest"，不受界面显示文字变化的影响.
+pub fn loan_to_json(loan: &loan::Loan) -> serde_json::Value {
+    serde_json::json!({
+        "loan_type": loan.loan_type.as_str(),
+        "unit": loan.unit.as_str(),
+        "loan_year": loan.loan_year,
+        "loan_money": loan.loan_money,
+        "loan_rate": loan.loan_rate,
+        "rate_spread_bp": loan.rate_spread_bp,
+        "graduation": loan.graduation,
+        "payment_frequency": loan.payment_frequency.as_str(),
+        "rate_convention": loan.rate_convention.as_str(),
+        "compounding": loan.compounding.as_str(),
+        "start_date": loan.start_date.map(|d| d.to_string()),
+        "first_payment_date": loan.first_payment_date.map(|d| d.to_string()),
+        "day_count": loan.day_count.as_str(),
+        "fees": {
+            "origination": loan.fees.origination,
+            "service": loan.fees.service,
+            "insurance": loan.fees.insurance,
+        },
+        "rate_changes": loan.rate_changes.iter().map(|c| serde_json::json!({
+            "month": c.month,
+            "rate": c.rate,
+        })).collect::<Vec<_>>(),
+        "prepayments": loan.prepayments.iter().map(|p| serde_json::json!({
+            "month": p.month,
+            "amount": p.amount,
+            "strategy": p.strategy.as_str(),
+        })).collect::<Vec<_>>(),
+    })
+}
+
+// loan_to_json 的逆运算，缺少的字段取默认值，无法识别的枚举值返回错误.
+pub fn loan_from_json(value: &serde_json::Value) -> Result<loan::Loan, String> {
+    let number = |value: &serde_json::Value, key: &str| value.get(key).and_then(|v| v.as_f64()).unwrap_or(0.0);
+    let month = |value: &serde_json::Value| value.get("month").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+    let text = |value: &serde_json::Value, key: &str| value.get(key).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+    let date = |key: &str| Date::parse(&text(value, key));
+    let list = |key: &str| value.get(key).and_then(|v| v.as_array()).cloned().unwrap_or_default();
+    let fees = value.get("fees").cloned().unwrap_or_default();
+    Ok(loan::Loan {
+        loan_type: enum_field(value, "loan_type")?,
+        loan_year: number(value, "loan_year"),
+        loan_money: number(value, "loan_money"),
+        loan_rate: number(value, "loan_rate"),
+        rate_spread_bp: number(value, "rate_spread_bp"),
+        graduation: number(value, "graduation"),
+        payment_frequency: enum_field(value, "payment_frequency")?,
+        rate_convention: enum_field(value, "rate_convention")?,
+        compounding: enum_field(value, "compounding")?,
+        start_date: date("start_date"),
+        first_payment_date: date("first_payment_date"),
+        day_count: enum_field(value, "day_count")?,
+        fees: loan::Fees {
+            origination: number(&fees, "origination"),
+            service: number(&fees, "service"),
+            insurance: number(&fees, "insurance"),
+        },
+        rate_changes: list("rate_changes").iter().map(|c| loan::RateChange {
+            month: month(c),
+            rate: number(c, "rate"),
+        }).collect(),
+        prepayments: list("prepayments").iter().map(|p| Ok(loan::Prepayment {
+            month: month(p),
+            amount: number(p, "amount"),
+            strategy: enum_field(p, "strategy")?,
+        })).collect::<Result<_, String>>()?,
+        unit: enum_field(value, "unit")?,
+        ..loan::Loan::new()
+    })
+}
+
+// 按 as_str 的标识读取枚举值，缺少该字段时取默认值.
+fn enum_field<T: Default + std::str::FromStr<Err = String>>(value: &serde_json::Value, key: &str) -> Result<T, String> {
+    match value.get(key) {
+        None | Some(serde_json::Value::Null) => Ok(T::default()),
+        Some(serde_json::Value::String(id)) => id.parse(),
+        Some(other) => Err(format!("{} 的值无效: {}", key, other)),
+    }
+}
+
+fn to_markdown(loan: &loan::Loan) -> String {
+    let row = |fields: &[String]| {
+        let fields: Vec<String> = fields.iter().map(|field| markdown_cell(field)).collect();
+        format!("| {} |\n", fields.join(" | "))
+    };
+    let mut out = String::from("| 参数 | 值 |\n| --- | --- |\n");
+    for (name, value) in parameters(loan) {
+        out.push_str(&row(&[name, value]));
+    }
+    out.push('\n');
+    let header = schedule_header(loan);
+    out.push_str(&row(&header));
+    out.push_str(&format!("|{}\n", " ---: |".repeat(header.len())));
+    for p in &loan.schedule {
+        out.push_str(&row(&schedule_row(loan, p)));
+    }
+    out
+}
+
+// 表格单元格中的竖线需要转义，换行替换为空格.
+fn markdown_cell(field: &str) -> String {
+    field.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+// 保存导出的文本：桌面端弹出保存文件对话框，取消时不做任何操作.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save(format: ExportFormat, contents: &str) -> Result<(), String> {
+    let path = rfd::FileDialog::new()
+        .set_file_name(&file_name(format))
+        .add_filter(format.to_str(), &[format.extension()])
+        .save_file();
+    match path {
+        Some(path) => std::fs::write(path, contents).map_err(|e| e.to_string()),
+        None => Ok(()),
+    }
+}
+
+// 下载开始后释放下载链接的延迟(毫秒).
+#[cfg(target_arch = "wasm32")]
+const REVOKE_DELAY_MS: i32 = 10_000;
+
+// 保存导出的文本：浏览器中以下载文件的方式保存.
+#[cfg(target_arch = "wasm32")]
+pub fn save(format: ExportFormat, contents: &str) -> Result<(), String> {
+    use wasm_bindgen::JsCast;
+
+    let js_error = |e: wasm_bindgen::JsValue| format!("{:?}", e);
+    let parts = js_sys::Array::of1(&wasm_bindgen::JsValue::from_str(contents));
+    let mut options = web_sys::BlobPropertyBag::new();
+    options.type_(format.mime_type());
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options).map_err(js_error)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(js_error)?;
+
+    let window = web_sys::window().ok_or_else(|| "无法访问浏览器页面".to_string())?;
+    let document = window.document().ok_or_else(|| "无法访问浏览器页面".to_string())?;
+    let anchor = document
+        .create_element("a")
+        .map_err(js_error)?
+        .dyn_into::<web_sys::HtmlAnchorElement>()
+        .map_err(|_| "无法创建下载链接".to_string())?;
+    anchor.set_href(&url);
+    anchor.set_download(&file_name(format));
+    anchor.click();
+    // 立即撤销链接会使部分浏览器取消下载，延迟一段时间后再释放.
+    let revoke = wasm_bindgen::closure::Closure::once_into_js(move || {
+        let _ = web_sys::Url::revoke_object_url(&url);
+    });
+    window
+        .set_timeout_with_callback_and_timeout_and_arguments_0(revoke.unchecked_ref(), REVOKE_DELAY_MS)
+        .map(|_| ())
+        .map_err(js_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 100 万元、30 年、年利率 4.9% 的等额本息贷款，手续费 2 万元.
+    fn mortgage() -> loan::Loan {
+        let mut loan = loan::Loan { loan_year: 30.0, loan_money: 100.0, loan_rate: 4.9, ..loan::Loan::new() };
+        loan.fees.origination = 2.0;
+        loan.calc().unwrap();
+        loan
+    }
+
+    #[test]
+    fn csv() {
+        let text = export(&mortgage(), ExportFormat::Csv);
+        assert!(text.starts_with('\u{feff}'));
+        let lines: Vec<&str> = text.trim_start_matches('\u{feff}').split("\r\n").collect();
+        assert_eq!(lines[0], "还款方式,等额本息");
+        assert!(lines.contains(&"贷款利率(%),4.9000"));
+        // 金额精确到分：万元保留 6 位小数，利率保留 4 位小数.
+        assert!(lines.contains(&"首月还款(万元),0.530727"), "{}", text);
+        assert!(lines.iter().any(|line| line.starts_with("年化利率 APR(%),5.") && line.len() == "年化利率 APR(%),5.0000".len()));
+        let header = lines.iter().position(|line| line.starts_with("期数,")).unwrap();
+        assert_eq!(lines[header + 1], "1,,4.9000,0.530727,0.122393,0.408333,0.000000,99.877607");
+        assert_eq!(lines.len(), header + 1 + 360 + 1);
+        assert_eq!(lines.last(), Some(&""));
+    }
+
+    #[test]
+    fn csv_fields() {
+        assert_eq!(csv_field("每期服务费"), "每期服务费");
+        assert_eq!(csv_field("1,234"), "\"1,234\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("a\nb"), "\"a\nb\"");
+    }
+
+    #[test]
+    fn markdown() {
+        let loan = mortgage();
+        let text = export(&loan, ExportFormat::Markdown);
+        assert!(text.starts_with("| 参数 | 值 |\n| --- | --- |\n| 还款方式 | 等额本息 |\n"));
+        assert!(text.contains("| 1 |  | 4.9000 | 0.530727 | 0.122393 | 0.408333 | 0.000000 | 99.877607 |\n"));
+        assert_eq!(text.lines().count(), 2 + parameters(&loan).len() + 1 + 2 + loan.schedule.len());
+        assert_eq!(markdown_cell("a|b\nc"), "a\\|b c");
+    }
+
+    #[test]
+    fn json() {
+        let loan = mortgage();
+        let value: serde_json::Value = serde_json::from_str(&export(&loan, ExportFormat::Json)).unwrap();
+        assert_eq!(value["loan_type"], "EqualInterest");
+        assert_eq!(value["currency_symbol"], "¥");
+        assert_eq!(value["schedule"].as_array().map(Vec::len), Some(360));
+        assert_eq!(value["result"]["first_payment"].as_f64(), Some(loan.result.as_ref().unwrap().first_payment));
+        // 导出的 JSON 可以读回贷款的输入.
+        let inputs = loan::Loan { result: None, schedule: Vec::new(), ..loan };
+        assert!(loan_from_json(&value) == Ok(inputs));
+        // 无法识别的枚举值报错，而不是取默认值.
+        let mut value = value;
+        value["unit"] = "Florin".into();
+        assert_eq!(loan_from_json(&value).err(), Some("未知的金额单位: Florin".to_string()));
+        value["unit"] = 1.into();
+        assert!(loan_from_json(&value).is_err());
+    }
+}
@@ -4,4 +4,13 @@ pub mod math_exp;
 pub mod calc_state;
 pub mod calc_config;
 pub mod loan;
-
+pub mod currency;
+pub mod export;
+pub mod solver;
+pub mod rate;
+pub mod date;
+pub mod cashflow;
+pub mod savings;
+pub mod tvm;
+pub mod affordability;
+pub mod scenario;
@@ -1,20 +1,375 @@
+use crate::cashflow;
+use crate::currency;
+use crate::date::{Date, DayCount};
+use crate::rate;
+use crate::solver;
+use crate::tvm;
 
-#[derive(Default,PartialEq)]
+#[derive(Default,Clone,Copy,Debug,PartialEq)]
 pub enum LoanType{
     #[default]
     EqualInterest,
     EqualPrincipal,
+    // 按月付息，到期一次偿还全部本金.
+    InterestOnly,
+    // 到期一次还本付息，期间不还款，利息按单利计算.
+    LumpSum,
+    // 等比递增：每年的月供比上一年增加固定比例.
+    // 递增还款不允许负摊还：首期还款必须高于首期利息，此后剩余本金逐期减少.
+    GraduatedGeometric,
+    // 等额递增：每年的月供比上一年增加固定金额，同样不允许负摊还.
+    GraduatedArithmetic,
+    // 双周供：每两周还款一次，每次为等额本息月供的一半，不受还款频率设置的影响.
+    BiWeekly,
 }
 
-#[derive(Default,PartialEq)]
+impl LoanType{
+    pub const ALL: [LoanType; 7] = [
+        LoanType::EqualInterest,
+        LoanType::EqualPrincipal,
+        LoanType::InterestOnly,
+        LoanType::LumpSum,
+        LoanType::GraduatedGeometric,
+        LoanType::GraduatedArithmetic,
+        LoanType::BiWeekly,
+    ];
+
+    // 组合贷款和方案对比中可用的还款方式，各期需按月对齐，不包括双周供.
+    pub const MONTHLY: [LoanType; 6] = [
+        LoanType::EqualInterest,
+        LoanType::EqualPrincipal,
+        LoanType::InterestOnly,
+        LoanType::LumpSum,
+        LoanType::GraduatedGeometric,
+        LoanType::GraduatedArithmetic,
+    ];
+
+    pub fn to_str(&self) -> &str{
+        match self{
+            LoanType::EqualInterest => "等额本息",
+            LoanType::EqualPrincipal => "等额本金",
+            LoanType::InterestOnly => "先息后本",
+            LoanType::LumpSum => "到期还本付息",
+            LoanType::GraduatedGeometric => "等比递增",
+            LoanType::GraduatedArithmetic => "等额递增",
+            LoanType::BiWeekly => "双周供",
+        }
+    }
+
+    // 保存方案和导出 JSON 时使用的标识，不随界面文字变化，不能修改.
+    pub fn as_str(&self) -> &'static str{
+        match self{
+            LoanType::EqualInterest => "EqualInterest",
+            LoanType::EqualPrincipal => "EqualPrincipal",
+            LoanType::InterestOnly => "InterestOnly",
+            LoanType::LumpSum => "LumpSum",
+            LoanType::GraduatedGeometric => "GraduatedGeometric",
+            LoanType::GraduatedArithmetic => "GraduatedArithmetic",
+            LoanType::BiWeekly => "BiWeekly",
+        }
+    }
+
+    // 是否为需要设置每年增幅的递增还款.
+    pub fn is_graduated(&self) -> bool{
+        matches!(self, LoanType::GraduatedGeometric | LoanType::GraduatedArithmetic)
+    }
+}
+
+impl std::str::FromStr for LoanType{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>{
+        Self::ALL.iter().copied().find(|v| v.as_str() == s).ok_or_else(|| format!("未知的还款方式: {}", s))
+    }
+}
+
+// 每期的还款明细.
+#[derive(Default,PartialEq,Clone,Debug)]
+pub struct Payment{
+    // 期数，从 1 开始.
+    pub period: u32,
+    // 本期还款额.
+    pub payment: f64,
+    // 本期偿还的本金.
+    pub principal: f64,
+    // 本期偿还的利息.
+    pub interest: f64,
+    // 本期提前偿还的本金.
+    pub prepayment: f64,
+    // 本期还款后的剩余本金.
+    pub balance: f64,
+    // 本期执行的年利率(%).
+    pub rate: f64,
+    // 还款日期，未指定起始日期时为空.
+    pub date: Option<Date>,
+}
+
+// 贷款的计算结果，金额单位与贷款金额相同.
+#[derive(Default,PartialEq,Clone,Debug)]
+pub struct LoanResult{
+    // 首月还款额.
+    pub first_payment: f64,
+    // 末月还款额.
+    pub last_payment: f64,
+    // 月均还款额.
+    pub average_payment: f64,
+    // 利息总额.
+    pub total_interest: f64,
+    // 还款总额，包括提前还款.
+    pub total_paid: f64,
+    // 与不提前还款相比节省的利息.
+    pub interest_saved: f64,
+    // 各项费用的总额.
+    pub total_fees: f64,
+    // 包括费用在内的年化利率(APR，%)：每期内部收益率乘以每年期数.
+    pub apr: Option<f64>,
+    // 包括费用在内的实际年利率(%)：每期内部收益率按复利折算为一年.
+    pub effective_annual_rate: Option<f64>,
+}
+
+impl LoanResult{
+    // 根据还款计划汇总计算结果.
+    pub fn from_schedule(schedule: &[Payment]) -> Option<Self>{
+        let first = schedule.first()?;
+        let last = schedule.last()?;
+        let total_payment: f64 = schedule.iter().map(|p| p.payment).sum();
+        let total_prepayment: f64 = schedule.iter().map(|p| p.prepayment).sum();
+        Some(Self{
+            first_payment: first.payment,
+            last_payment: last.payment,
+            average_payment: total_payment / schedule.len() as f64,
+            total_interest: schedule.iter().map(|p| p.interest).sum(),
+            total_paid: total_payment + total_prepayment,
+            interest_saved: 0.0,
+            total_fees: 0.0,
+            apr: None,
+            effective_annual_rate: None,
+        })
+    }
+}
+
+// 提前还款后的处理方式.
+#[derive(Default,Clone,Copy,Debug,PartialEq)]
+pub enum PrepayStrategy{
+    // 月供不变，缩短还款期限.
+    #[default]
+    ShortenTerm,
+    // 还款期限不变，减少月供.
+    ReducePayment,
+}
+
+impl PrepayStrategy{
+    pub const ALL: [PrepayStrategy; 2] = [PrepayStrategy::ShortenTerm, PrepayStrategy::ReducePayment];
+
+    pub fn to_str(&self) -> &str{
+        match self{
+            PrepayStrategy::ShortenTerm => "缩短年限",
+            PrepayStrategy::ReducePayment => "减少月供",
+        }
+    }
+
+    // 保存和导出时使用的标识.
+    pub fn as_str(&self) -> &'static str{
+        match self{
+            PrepayStrategy::ShortenTerm => "ShortenTerm",
+            PrepayStrategy::ReducePayment => "ReducePayment",
+        }
+    }
+}
+
+impl std::str::FromStr for PrepayStrategy{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>{
+        Self::ALL.iter().copied().find(|v| v.as_str() == s).ok_or_else(|| format!("未知的提前还款方式: {}", s))
+    }
+}
+
+// 一笔提前还款.
+#[derive(Default,Clone,Debug,PartialEq)]
+pub struct Prepayment{
+    // 在第几个月的正常还款之后提前还款.
+    pub month: u32,
+    // 提前还款的金额.
+    pub amount: f64,
+    pub strategy: PrepayStrategy,
+}
+
+// 浮动利率的一次调整.
+#[derive(Default,Clone,Debug,PartialEq)]
+pub struct RateChange{
+    // 从第几个月开始执行新的利率.
+    pub month: u32,
+    // 新的年利率(%)，使用加点时为基准利率.
+    pub rate: f64,
+}
+
+// 贷款的费用，金额单位与贷款金额相同.
+#[derive(Default,Clone,Debug,PartialEq)]
+pub struct Fees{
+    // 放款时一次性收取的手续费，从实际到手的金额中扣除.
+    pub origination: f64,
+    // 每期随还款收取的服务费.
+    pub service: f64,
+    // 每期随还款收取的保险费.
+    pub insurance: f64,
+}
+
+// 贷款输入中出错的字段.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum LoanField{
+    Year,
+    Money,
+    Rate,
+    // 第几笔提前还款，从 0 开始.
+    Prepayment(usize),
+    // 第几次利率调整，从 0 开始.
+    RateChange(usize),
+    Graduation,
+    FirstPaymentDate,
+    Fees,
+    // 还款方式或还款频率.
+    Frequency,
+    // 反求时的目标月供.
+    Payment,
+}
+
+// 贷款输入的校验错误，每个字段最多一个.
+#[derive(Clone,Debug,PartialEq)]
+pub enum LoanError{
+    // 贷款年限不足一个月或超过上限.
+    InvalidYear,
+    // 贷款金额不大于 0.
+    InvalidMoney,
+    // 贷款利率为负数或超过上限.
+    InvalidRate,
+    // 提前还款的月份超出贷款期限或金额不大于 0.
+    InvalidPrepayment(usize),
+    // 利率调整的月份超出贷款期限或调整后的利率超出范围.
+    InvalidRateChange(usize),
+    // 递增还款的每年增幅超出范围，或首期还款不高于首期利息.
+    InvalidGraduation,
+    // 首次还款日不晚于起始日期.
+    InvalidFirstPaymentDate,
+    // 费用为负数或手续费不小于贷款金额.
+    InvalidFees,
+    // 组合贷款或方案对比中的贷款不是按月还款.
+    InvalidFrequency,
+    // 反求时的目标月供不大于 0.
+    InvalidPayment,
+    // 在允许的范围内找不到满足目标月供的解.
+    NoSolution(SolveFor),
+}
+
+impl LoanError{
+    pub fn field(&self) -> LoanField{
+        match self{
+            LoanError::InvalidYear => LoanField::Year,
+            LoanError::InvalidMoney => LoanField::Money,
+            LoanError::InvalidRate => LoanField::Rate,
+            LoanError::InvalidPrepayment(i) => LoanField::Prepayment(*i),
+            LoanError::InvalidRateChange(i) => LoanField::RateChange(*i),
+            LoanError::InvalidGraduation => LoanField::Graduation,
+            LoanError::InvalidFirstPaymentDate => LoanField::FirstPaymentDate,
+            LoanError::InvalidFees => LoanField::Fees,
+            LoanError::InvalidFrequency => LoanField::Frequency,
+            LoanError::InvalidPayment | LoanError::NoSolution(_) => LoanField::Payment,
+        }
+    }
+}
+
+impl std::fmt::Display for LoanError{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result{
+        match self{
+            LoanError::InvalidYear => write!(f, "贷款年限必须在 1 个月到 {} 年之间", MAX_YEAR),
+            LoanError::InvalidMoney => write!(f, "贷款金额必须大于 0"),
+            LoanError::InvalidRate => write!(f, "贷款利率必须在 0% 到 {}% 之间", MAX_RATE),
+            LoanError::InvalidPrepayment(i) => write!(f, "第 {} 笔提前还款的月份必须在贷款期限内且金额大于 0", i + 1),
+            LoanError::InvalidRateChange(i) => write!(f, "第 {} 次利率调整的月份必须在贷款期限内，且加点后的利率在 0% 到 {}% 之间", i + 1, MAX_RATE),
+            LoanError::InvalidGraduation => write!(f, "等比递增的年增幅必须在 -{0}% 到 {0}% 之间，且递增还款的首期还款必须高于首期利息", MAX_GRADUATION),
+            LoanError::InvalidFirstPaymentDate => write!(f, "首次还款日必须晚于起始日期"),
+            LoanError::InvalidFees => write!(f, "费用不能为负数，且手续费必须小于贷款金额"),
+            LoanError::InvalidFrequency => write!(f, "组合贷款和方案对比只支持按月还款，不能使用双周供"),
+            LoanError::InvalidPayment => write!(f, "目标月供必须大于 0"),
+            LoanError::NoSolution(unknown) => write!(f, "在允许的范围内找不到满足目标月供的{}", unknown.to_str()),
+        }
+    }
+}
+
+// 贷款计算的未知量：正向计算月供，或根据目标月供反求其他输入.
+#[derive(Default,Clone,Copy,Debug,PartialEq)]
+pub enum SolveFor{
+    #[default]
+    Payment,
+    Rate,
+    Year,
+    Money,
+}
+
+impl SolveFor{
+    pub fn to_str(&self) -> &str{
+        match self{
+            SolveFor::Payment => "月供",
+            SolveFor::Rate => "贷款利率",
+            SolveFor::Year => "贷款年限",
+            SolveFor::Money => "贷款金额",
+        }
+    }
+
+    // 作为未知量的输入字段.
+    pub fn field(&self) -> Option<LoanField>{
+        match self{
+            SolveFor::Payment => None,
+            SolveFor::Rate => Some(LoanField::Rate),
+            SolveFor::Year => Some(LoanField::Year),
+            SolveFor::Money => Some(LoanField::Money),
+        }
+    }
+}
+
+// 贷款年限的上限(年).
+pub const MAX_YEAR: f64 = 50.0;
+// 贷款金额的上限(万元)，其他单位按基本单位换算后比较.
+pub const MAX_MONEY: f64 = 1_000_000.0;
+// 贷款年利率的上限(%).
+pub const MAX_RATE: f64 = 36.0;
+
+// 等比递增每年增幅的上限(%).
+pub const MAX_GRADUATION: f64 = 50.0;
+
+// 剩余本金小于该值时视为已还清.
+const BALANCE_EPSILON: f64 = 1e-9;
+
+#[derive(Default,Clone,PartialEq)]
 pub struct Loan{
     pub loan_type: LoanType,
-    pub loan_year: f32,
-    pub loan_money: f32,
-    pub loan_rate: f32,
-    pub money_per_month: String,
-    pub total_interest: String,
-    pub total_money: String,
+    pub loan_year: f64,
+    pub loan_money: f64,
+    pub loan_rate: f64,
+    pub prepayments: Vec<Prepayment>,
+    // 浮动利率的调整，按月份生效.
+    pub rate_changes: Vec<RateChange>,
+    // 在基准利率上的加点(基点，1 基点 = 0.01%)，可以为负数.
+    pub rate_spread_bp: f64,
+    // 递增还款的每年增幅：等比递增为百分比(%)，等额递增为金额.
+    pub graduation: f64,
+    // 还款频率，默认按月还款.
+    pub payment_frequency: rate::Frequency,
+    // 计息频率，只对名义年利率有效.
+    pub compounding: rate::Frequency,
+    // 年利率为名义年利率还是实际年利率.
+    pub rate_convention: rate::RateConvention,
+    // 起始日期(放款日)，为空时还款计划只有期数.
+    pub start_date: Option<Date>,
+    // 首次还款日，为空时为起始日期之后的一期.
+    pub first_payment_date: Option<Date>,
+    // 首期的计息方式，首次还款日与起始日期不足或超过一期时按天计息.
+    pub day_count: DayCount,
+    pub fees: Fees,
+    // 金额的单位：贷款金额、递增额、费用、提前还款及计算结果都以该单位计.
+    pub unit: currency::MoneyUnit,
+    pub result: Option<LoanResult>,
+    pub schedule: Vec<Payment>,
 }
 
 impl Loan{
@@ -24,21 +379,185 @@ impl Loan{
             loan_year: 0.0,
             loan_money: 0.0,
             loan_rate: 0.0,
-            money_per_month: String::new(),
-            total_interest: String::new(),
-            total_money: String::new(),
+            prepayments: Vec::new(),
+            rate_changes: Vec::new(),
+            rate_spread_bp: 0.0,
+            graduation: 0.0,
+            payment_frequency: rate::Frequency::Monthly,
+            compounding: rate::Frequency::Monthly,
+            rate_convention: rate::RateConvention::Nominal,
+            start_date: None,
+            first_payment_date: None,
+            day_count: DayCount::Periodic,
+            fees: Fees::default(),
+            unit: currency::MoneyUnit::Wan,
+            result: None,
+            schedule: Vec::new(),
+        }
+    }
+
+    // 以当前单位计的贷款金额上限.
+    pub fn max_money(&self) -> f64{
+        self.unit.from_base(MAX_MONEY * currency::WAN)
+    }
+
+    // 切换金额单位。同一货币内换算已输入的金额，例如 100 万元换算为 1000000 元；
+    // 切换到其他货币时金额保持不变，见 MoneyUnit::conversion_to.
+    pub fn set_unit(&mut self, unit: currency::MoneyUnit){
+        let factor = self.unit.conversion_to(unit);
+        self.loan_money *= factor;
+        if self.loan_type == LoanType::GraduatedArithmetic {
+            self.graduation *= factor;
+        }
+        self.fees.origination *= factor;
+        self.fees.service *= factor;
+        self.fees.insurance *= factor;
+        for prepayment in &mut self.prepayments {
+            prepayment.amount *= factor;
         }
+        self.unit = unit;
+        self.result = None;
+        self.schedule.clear();
     }
     
-    pub fn calc(&mut self){
+    // 按月还款时计算，否则清空结果并返回错误。组合贷款和方案对比按月对齐各期还款.
+    pub fn calc_monthly(&mut self) -> Result<(), Vec<LoanError>>{
+        if self.schedule_frequency() != rate::Frequency::Monthly {
+            self.schedule.clear();
+            self.result = None;
+            return Err(vec![LoanError::InvalidFrequency]);
+        }
+        self.calc()
+    }
+
+    // 校验输入，返回所有出错字段的错误.
+    pub fn validate(&self) -> Result<(), Vec<LoanError>>{
+        let mut errors = Vec::new();
+        let month = (self.loan_year * 12.0).round();
+        if !(1.0..=MAX_YEAR * 12.0).contains(&month){
+            errors.push(LoanError::InvalidYear);
+        }
+        if !(self.loan_money > 0.0 && self.loan_money <= self.max_money()){
+            errors.push(LoanError::InvalidMoney);
+        }
+        if !(0.0..=MAX_RATE).contains(&(self.loan_rate + self.rate_spread_bp / 100.0)){
+            errors.push(LoanError::InvalidRate);
+        }
+        // 年限、金额和利率有效时才能计算首期还款.
+        let graduation_valid = self.graduation_in_range()
+            && (!self.loan_type.is_graduated() || !errors.is_empty() || self.covers_first_interest());
+        if !graduation_valid {
+            errors.push(LoanError::InvalidGraduation);
+        }
+        let fees = &self.fees;
+        if fees.origination < 0.0 || fees.service < 0.0 || fees.insurance < 0.0
+            || (self.loan_money > 0.0 && fees.origination >= self.loan_money) {
+            errors.push(LoanError::InvalidFees);
+        }
+        if let (Some(start), Some(first)) = (self.start_date, self.first_payment_date) {
+            if first <= start {
+                errors.push(LoanError::InvalidFirstPaymentDate);
+            }
+        }
+        for (i, p) in self.prepayments.iter().enumerate() {
+            if !(1..=self.months()).contains(&p.month) || p.amount <= 0.0 {
+                errors.push(LoanError::InvalidPrepayment(i));
+            }
+        }
+        for (i, c) in self.rate_changes.iter().enumerate() {
+            let rate = c.rate + self.rate_spread_bp / 100.0;
+            if !(1..=self.months()).contains(&c.month) || !(0.0..=MAX_RATE).contains(&rate) {
+                errors.push(LoanError::InvalidRateChange(i));
+            }
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    // 递增还款的每年增幅是否在允许的范围内.
+    fn graduation_in_range(&self) -> bool{
         match self.loan_type{
-            LoanType::EqualInterest => {
-                self.calc_equal_interest();
-            },
-            LoanType::EqualPrincipal => {
-                self.calc_equal_principal();
-            },
+            LoanType::GraduatedGeometric => self.graduation.abs() <= MAX_GRADUATION,
+            LoanType::GraduatedArithmetic => self.graduation.is_finite(),
+            _ => true,
+        }
+    }
+
+    // 递增还款的首期还款是否高于首期利息。增幅过大时首期还款过低甚至为负，剩余本金会先增后减.
+    fn covers_first_interest(&self) -> bool{
+        let annual_rate = self.annual_rate_at(1);
+        let installment = self.installment(self.loan_money, annual_rate, 1, self.periods());
+        installment > 0.0 && installment > self.loan_money * self.periodic_rate(annual_rate)
+    }
+
+    // 计算还款计划和结果。输入无效时清空结果并返回错误.
+    pub fn calc(&mut self) -> Result<(), Vec<LoanError>>{
+        self.schedule.clear();
+        self.result = None;
+        self.validate()?;
+        self.schedule = self.amortize(&self.prepayments);
+        self.result = LoanResult::from_schedule(&self.schedule).map(|mut result| {
+            if !self.prepayments.is_empty() {
+                let baseline: f64 = self.amortize(&[]).iter().map(|p| p.interest).sum();
+                result.interest_saved = baseline - result.total_interest;
+            }
+            let flows = self.cash_flows();
+            let periods = flows.len() as f64 - 1.0;
+            result.total_fees = self.fees.origination + (self.fees.service + self.fees.insurance) * periods;
+            if let Some(rate) = cashflow::irr(&flows) {
+                let per_year = self.periods_per_year() as f64;
+                result.apr = Some(rate * per_year * 100.0);
+                result.effective_annual_rate = Some(((1.0 + rate).powf(per_year) - 1.0) * 100.0);
+            }
+            result
+        });
+        Ok(())
+    }
+
+    // 借款人每期的实际现金流：第 0 期为扣除手续费后到手的金额，之后为还款及费用(负数).
+    pub fn cash_flows(&self) -> Vec<f64>{
+        let last = self.schedule.last().map_or(0, |p| p.period) as usize;
+        let mut flows = vec![-(self.fees.service + self.fees.insurance); last + 1];
+        flows[0] = self.loan_money - self.fees.origination;
+        for p in &self.schedule {
+            flows[p.period as usize] -= p.payment + p.prepayment;
+        }
+        flows
+    }
+
+    // 校验反求所需的输入，忽略作为未知量的字段.
+    // 递增还款的首期还款取决于未知量，只检查增幅的范围，求解后由 calc 检查首期还款是否高于首期利息.
+    pub fn validate_solve(&self, unknown: SolveFor, payment: f64) -> Result<(), Vec<LoanError>>{
+        let mut errors: Vec<LoanError> = self.validate().err().unwrap_or_default()
+            .into_iter()
+            .filter(|e| Some(e.field()) != unknown.field())
+            .filter(|e| *e != LoanError::InvalidGraduation || !self.graduation_in_range())
+            .collect();
+        if unknown != SolveFor::Payment && payment <= 0.0 {
+            errors.push(LoanError::InvalidPayment);
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    // 根据目标首期还款额 payment 反求 unknown 指定的输入，写回后重新计算还款计划.
+    // 反求时忽略提前还款.
+    pub fn solve(&mut self, unknown: SolveFor, payment: f64) -> Result<(), Vec<LoanError>>{
+        if unknown == SolveFor::Payment {
+            return self.calc();
+        }
+        self.schedule.clear();
+        self.result = None;
+        self.validate_solve(unknown, payment)?;
+        let solved = match unknown{
+            SolveFor::Rate => solve_rate(self, payment).map(|rate| self.loan_rate = rate),
+            SolveFor::Year => solve_periods(self, payment)
+                .map(|periods| self.loan_year = periods as f64 / self.periods_per_year() as f64),
+            SolveFor::Money => solve_money(self, payment).map(|money| self.loan_money = money),
+            SolveFor::Payment => unreachable!(),
+        };
+        if solved.is_none() {
+            return Err(vec![LoanError::NoSolution(unknown)]);
         }
+        self.calc()
     }
 
     pub fn reset(&mut self){
@@ -46,43 +565,789 @@ impl Loan{
         self.loan_year = 0.0;
         self.loan_money = 0.0;
         self.loan_rate = 0.0;
-        self.money_per_month = String::new();
-        self.total_interest = String::new();
-        self.total_money = String::new();
-    }
-
-    fn calc_equal_interest(&mut self){
-        let month = self.loan_year * 12.0;
-        let rate = self.loan_rate / 100.0 / 12.0;
-        let money = self.loan_money;
-        let mut total_interest = 0.0;
-        let mut money_per_month = 0.0;
-        let mut total_money = 0.0;
-        if month > 0.0 && rate > 0.0 && money > 0.0{
-            money_per_month = money * rate * (1.0 + rate).powf(month) / ((1.0 + rate).powf(month) - 1.0);
-            total_interest = money_per_month * month - money;
-            total_money = money_per_month * month;
-        }
-        self.money_per_month = money_per_month.to_string();
-        self.total_interest = total_interest.to_string();
-        self.total_money = total_money.to_string();
-    }
-
-    fn calc_equal_principal(&mut self){
-        let month = self.loan_year * 12.0;
-        let rate = self.loan_rate / 100.0 / 12.0;
-        let money = self.loan_money;
-        let mut total_interest = 0.0;
-        let mut money_per_month = 0.0;
-        let mut total_money = 0.0;
-        if month > 0.0 && rate > 0.0 && money > 0.0{
-            money_per_month = money / month + money * rate;
-            total_interest = money * rate * (month + 1.0) / 2.0;
-            total_money = money + total_interest;
-        }
-        self.money_per_month = money_per_month.to_string();
-        self.total_interest = total_interest.to_string();
-        self.total_money = total_money.to_string();
-    }
-
-}
\ No newline at end of file
+        self.prepayments.clear();
+        self.rate_changes.clear();
+        self.rate_spread_bp = 0.0;
+        self.graduation = 0.0;
+        self.payment_frequency = rate::Frequency::Monthly;
+        self.compounding = rate::Frequency::Monthly;
+        self.rate_convention = rate::RateConvention::Nominal;
+        self.start_date = None;
+        self.first_payment_date = None;
+        self.day_count = DayCount::Periodic;
+        self.fees = Fees::default();
+        self.result = None;
+        self.schedule.clear();
+    }
+
+    // 贷款期限(月)，提前还款和利率调整都按月份指定.
+    pub fn months(&self) -> u32{
+        (self.loan_year * 12.0).round() as u32
+    }
+
+    // 实际的还款频率，双周供固定为每两周.
+    pub fn schedule_frequency(&self) -> rate::Frequency{
+        match self.loan_type{
+            LoanType::BiWeekly => rate::Frequency::BiWeekly,
+            _ => self.payment_frequency,
+        }
+    }
+
+    // 每年的还款期数.
+    pub fn periods_per_year(&self) -> u32{
+        self.schedule_frequency().per_year()
+    }
+
+    // 贷款总期数.
+    pub fn periods(&self) -> u32{
+        (self.loan_year * self.periods_per_year() as f64).round() as u32
+    }
+
+    // 第 period 期所在的月份.
+    fn month_of(&self, period: u32) -> u32{
+        let per_year = self.periods_per_year();
+        (period * 12).div_ceil(per_year)
+    }
+
+    // 第 period 期的还款日期，未指定起始日期时为 None.
+    // 每期都从同一个锚定日(首次还款日或起始日期)推算，避免 1 月 28 日起始的贷款在 2 月 28 日之后漂移到月末.
+    pub fn payment_date(&self, period: u32) -> Option<Date>{
+        match (self.first_payment_date, self.start_date) {
+            (Some(first), _) => Some(self.advance(first, period.saturating_sub(1))),
+            (None, Some(start)) => Some(self.advance(start, period)),
+            (None, None) => None,
+        }
+    }
+
+    // date 之后第 periods 期的日期。按月、季度、年还款时目标月份没有该日则取月末，date 为月末时结果也为月末.
+    fn advance(&self, date: Date, periods: u32) -> Date{
+        let n = periods as i32;
+        match self.schedule_frequency(){
+            rate::Frequency::Daily => date.add_days(n as i64),
+            rate::Frequency::Weekly => date.add_days(7 * n as i64),
+            rate::Frequency::BiWeekly => date.add_days(14 * n as i64),
+            frequency => date.add_months(n * 12 / frequency.per_year() as i32),
+        }
+    }
+
+    // 首期按天计息时的计息年数.
+    fn first_period_years(&self) -> Option<f64>{
+        let start = self.start_date?;
+        self.day_count.year_fraction(start, self.payment_date(1)?)
+    }
+
+    // 年利率(%)对应的每期利率，按利率的表示方式和计息频率换算.
+    fn periodic_rate(&self, annual_rate: f64) -> f64{
+        rate::periodic_rate_per_year(annual_rate / 100.0, self.rate_convention, self.compounding, self.periods_per_year())
+    }
+
+    // 生成还款计划，prepayments 为空时即为不提前还款的计划.
+    fn amortize(&self, prepayments: &[Prepayment]) -> Vec<Payment>{
+        let periods = self.periods();
+        let mut annual_rate = self.annual_rate_at(1);
+        let mut schedule = Vec::new();
+        let mut balance = self.loan_money;
+        let mut installment = self.installment(balance, annual_rate, 1, periods);
+        // 最后一期的期数，缩短年限的提前还款会使其提前.
+        let mut last_period = periods;
+        // 到期一次还本付息时尚未支付的利息.
+        let mut accrued = 0.0;
+        let mut period = 0;
+        while balance > BALANCE_EPSILON && period < last_period {
+            period += 1;
+            let month = self.month_of(period);
+            // 利率调整时按剩余期数重新计算月供.
+            if self.annual_rate_at(month) != annual_rate {
+                annual_rate = self.annual_rate_at(month);
+                installment = self.installment(balance, annual_rate, period, last_period);
+            }
+            let regular_interest = balance * self.periodic_rate(annual_rate);
+            let mut principal = match self.loan_type{
+                LoanType::EqualInterest | LoanType::BiWeekly => installment - regular_interest,
+                LoanType::EqualPrincipal => installment,
+                LoanType::GraduatedGeometric | LoanType::GraduatedArithmetic => {
+                    self.graduated_payment(installment, period) - regular_interest
+                },
+                LoanType::InterestOnly | LoanType::LumpSum => 0.0,
+            };
+            // 首期按天计息时本金不变，只调整利息.
+            let mut interest = regular_interest;
+            if period == 1 {
+                if let Some(years) = self.first_period_years() {
+                    interest = balance * annual_rate / 100.0 * years;
+                }
+            }
+            if self.loan_type == LoanType::LumpSum {
+                accrued += interest;
+                interest = 0.0;
+            }
+            // 最后一期还清剩余本金，消除舍入误差.
+            if period == last_period || principal > balance {
+                principal = balance;
+            }
+            balance -= principal;
+
+            // 提前还款在其所在月份内第一期的正常还款之后进行，按季度或按年还款时顺延到下一次还款.
+            let mut prepayment = 0.0;
+            let mut strategy = None;
+            let previous_month = if period == 1 { 0 } else { self.month_of(period - 1) };
+            for p in prepayments.iter().filter(|p| previous_month < p.month && p.month <= month) {
+                let amount = p.amount.min(balance);
+                prepayment += amount;
+                balance -= amount;
+                strategy = Some(p.strategy);
+            }
+            match strategy{
+                Some(PrepayStrategy::ReducePayment) => {
+                    installment = self.installment(balance, annual_rate, period + 1, last_period);
+                },
+                // 缩短年限时保持原月供不变，重新计算还清剩余本金所需的期数.
+                Some(PrepayStrategy::ShortenTerm) => {
+                    let remaining = self.remaining_periods(balance, annual_rate, installment, period);
+                    last_period = last_period.min(period.saturating_add(remaining));
+                },
+                None => {},
+            }
+
+            // 到期一次还本付息的利息在还清本金时一并支付，其余各期没有还款.
+            if self.loan_type == LoanType::LumpSum {
+                if balance <= BALANCE_EPSILON {
+                    interest = accrued;
+                } else if prepayment == 0.0 {
+                    continue;
+                }
+            }
+            schedule.push(Payment{
+                period,
+                payment: principal + interest,
+                principal,
+                interest,
+                prepayment,
+                balance: balance.max(0.0),
+                rate: annual_rate,
+                date: self.payment_date(period),
+            });
+        }
+        schedule
+    }
+
+    // 第 month 个月执行的年利率(%)，包括加点.
+    pub fn annual_rate_at(&self, month: u32) -> f64{
+        let base = self.rate_changes.iter()
+            .filter(|c| c.month <= month)
+            .max_by_key(|c| c.month)
+            .map_or(self.loan_rate, |c| c.rate);
+        base + self.rate_spread_bp / 100.0
+    }
+
+    // 递增还款第 period 期的还款额，base 为第一年的每期还款额.
+    fn graduated_payment(&self, base: f64, period: u32) -> f64{
+        let year = ((period - 1) / self.periods_per_year()) as f64;
+        match self.loan_type{
+            LoanType::GraduatedGeometric => base * (1.0 + self.graduation / 100.0).powf(year),
+            LoanType::GraduatedArithmetic => base + self.graduation * year,
+            _ => base,
+        }
+    }
+
+    // 第 period 期还款之后，保持每期还款 installment 不变时还清 balance 所需的期数.
+    fn remaining_periods(&self, balance: f64, annual_rate: f64, installment: f64, period: u32) -> u32{
+        if balance <= BALANCE_EPSILON {
+            return 0;
+        }
+        let rate = self.periodic_rate(annual_rate);
+        let n = match self.loan_type{
+            // 每期还款不足以支付利息时永远无法还清.
+            LoanType::EqualInterest | LoanType::BiWeekly => {
+                match tvm::periods(rate, balance, -installment, 0.0, tvm::PaymentTiming::End) {
+                    Some(n) => n,
+                    None => return u32::MAX,
+                }
+            },
+            LoanType::EqualPrincipal => balance / installment,
+            // 递增还款逐期模拟，直到还清或超过贷款期限.
+            LoanType::GraduatedGeometric | LoanType::GraduatedArithmetic => {
+                let mut remaining = balance;
+                let mut n = 0;
+                while remaining > BALANCE_EPSILON {
+                    if n >= self.periods() {
+                        return u32::MAX;
+                    }
+                    n += 1;
+                    remaining = remaining * (1.0 + rate) - self.graduated_payment(installment, period + n);
+                }
+                return n;
+            },
+            // 先息后本和到期一次还本付息的期限不变.
+            LoanType::InterestOnly | LoanType::LumpSum => return u32::MAX,
+        };
+        // 忽略浮点误差带来的极小尾数.
+        (n - 1e-9).ceil() as u32
+    }
+
+    // 在第 first 期到第 last 期内还清 balance 所需的每期还款：等额本息为每期还款额，等额本金为每期偿还的本金，
+    // 递增还款为第一年的每期还款额，先息后本和到期一次还本付息在到期前不还本金.
+    fn installment(&self, balance: f64, annual_rate: f64, first: u32, last: u32) -> f64{
+        if last < first {
+            return balance;
+        }
+        let n = (last - first + 1) as f64;
+        let rate = self.periodic_rate(annual_rate);
+        match self.loan_type{
+            LoanType::EqualInterest => -tvm::payment(n, rate, balance, 0.0, tvm::PaymentTiming::End),
+            LoanType::EqualPrincipal => balance / n,
+            // 双周供每期还款为按月等额本息月供的一半.
+            LoanType::BiWeekly => {
+                let monthly = Loan{
+                    loan_type: LoanType::EqualInterest,
+                    compounding: self.compounding,
+                    rate_convention: self.rate_convention,
+                    ..Loan::new()
+                };
+                let months = (n * 12.0 / self.periods_per_year() as f64).round().max(1.0) as u32;
+                monthly.installment(balance, annual_rate, 1, months) / 2.0
+            },
+            // 各期还款折现后之和等于剩余本金.
+            LoanType::GraduatedGeometric | LoanType::GraduatedArithmetic => {
+                let (mut annuity, mut graduated) = (0.0, 0.0);
+                let mut discount = 1.0;
+                for period in first..=last {
+                    discount /= 1.0 + rate;
+                    annuity += discount;
+                    graduated += discount * self.graduated_payment(1.0, period);
+                }
+                match self.loan_type{
+                    LoanType::GraduatedGeometric => balance / graduated,
+                    // 每期还款为 base + 增额 * 年数，graduated - annuity 为增额部分的折现值.
+                    _ => (balance - (graduated - annuity)) / annuity,
+                }
+            },
+            LoanType::InterestOnly | LoanType::LumpSum => 0.0,
+        }
+    }
+}
+
+// 首期还款额：等额本息为每期还款额，等额本金为第一期的本金加利息，到期一次还本付息为到期的还款总额.
+// 按 loan 的输入计算，忽略提前还款.
+pub fn first_payment(loan: &Loan) -> f64{
+    loan.amortize(&[]).first().map_or(0.0, |p| p.payment)
+}
+
+// 已知首期还款额，反求 loan 的基准年利率(%)。首期还款额随利率单调递增.
+pub fn solve_rate(loan: &Loan, payment: f64) -> Option<f64>{
+    let spread = loan.rate_spread_bp / 100.0;
+    let f = |rate: f64| first_payment(&Loan{ loan_rate: rate, ..loan.clone() }) - payment;
+    solver::find_root(f, -spread, MAX_RATE - spread)
+}
+
+// 已知首期还款额，反求贷款期数。期数为整数，取首期还款额不超过目标值的期数中最接近的一个.
+pub fn solve_periods(loan: &Loan, payment: f64) -> Option<u32>{
+    // 先息后本的首期只付利息，与期数无关.
+    if loan.loan_type == LoanType::InterestOnly {
+        return None;
+    }
+    let per_year = loan.periods_per_year();
+    let f = |periods: u32| {
+        first_payment(&Loan{ loan_year: periods as f64 / per_year as f64, ..loan.clone() }) - payment
+    };
+    let max_periods = MAX_YEAR as u32 * per_year;
+    let (first, last) = (f(1), f(max_periods));
+    // 多数还款方式的首期还款额随期数递减，到期一次还本付息则随期数递增.
+    let decreasing = first > last;
+    if first == last || (decreasing && last > 0.0) || (!decreasing && first > 0.0) {
+        return None;
+    }
+    // 二分查找满足条件的边界期数.
+    let (mut lo, mut hi) = (1, max_periods);
+    while lo < hi {
+        let mid = if decreasing { (lo + hi) / 2 } else { (lo + hi).div_ceil(2) };
+        match (decreasing, f(mid) <= 0.0) {
+            (true, true) => hi = mid,
+            (true, false) => lo = mid + 1,
+            (false, true) => lo = mid,
+            (false, false) => hi = mid - 1,
+        }
+    }
+    Some(lo)
+}
+
+// 已知首期还款额，反求可贷金额。首期还款额随贷款金额单调递增.
+pub fn solve_money(loan: &Loan, payment: f64) -> Option<f64>{
+    let f = |money: f64| first_payment(&Loan{ loan_money: money, ..loan.clone() }) - payment;
+    solver::find_root(f, 0.0, loan.max_money())
+}
+
+// 组合贷款中的一笔贷款，例如公积金贷款或商业贷款.
+#[derive(Default,PartialEq)]
+pub struct Tranche{
+    pub name: String,
+    pub loan: Loan,
+}
+
+// 组合贷款：由多笔利率和期限不同的贷款组成，按月合并还款.
+#[derive(PartialEq)]
+pub struct CombinationLoan{
+    pub tranches: Vec<Tranche>,
+    // 各笔贷款共用的金额单位.
+    pub unit: currency::MoneyUnit,
+    pub result: Option<LoanResult>,
+    pub schedule: Vec<Payment>,
+}
+
+impl Default for CombinationLoan{
+    fn default() -> Self{
+        Self::new()
+    }
+}
+
+impl CombinationLoan{
+    // 默认由公积金贷款和商业贷款组成.
+    pub fn new() -> Self{
+        let tranche = |name: &str, rate: f64| Tranche{
+            name: name.to_string(),
+            loan: Loan{
+                loan_year: 30.0,
+                loan_rate: rate,
+                ..Loan::new()
+            },
+        };
+        Self{
+            tranches: vec![tranche("公积金贷款", 3.1), tranche("商业贷款", 4.9)],
+            unit: currency::MoneyUnit::Wan,
+            result: None,
+            schedule: Vec::new(),
+        }
+    }
+
+    // 切换金额单位，并换算各笔贷款已输入的金额.
+    pub fn set_unit(&mut self, unit: currency::MoneyUnit){
+        for tranche in &mut self.tranches {
+            tranche.loan.set_unit(unit);
+        }
+        self.unit = unit;
+        self.result = None;
+        self.schedule.clear();
+    }
+
+    // 分别计算每笔贷款并合并还款计划。返回出错贷款的序号及其错误.
+    pub fn calc(&mut self) -> Result<(), Vec<(usize, LoanError)>>{
+        self.schedule.clear();
+        self.result = None;
+        let mut errors = Vec::new();
+        for (i, tranche) in self.tranches.iter_mut().enumerate() {
+            tranche.loan.unit = self.unit;
+            if let Err(e) = tranche.loan.calc_monthly() {
+                errors.extend(e.into_iter().map(|e| (i, e)));
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        let per_year = rate::Frequency::Monthly.per_year();
+        self.schedule = combine_schedules(self.tranches.iter().map(|t| t.loan.schedule.as_slice()), per_year);
+        self.result = LoanResult::from_schedule(&self.schedule).map(|mut result| {
+            result.interest_saved = self.tranches.iter()
+                .filter_map(|t| t.loan.result.as_ref())
+                .map(|r| r.interest_saved)
+                .sum();
+            result
+        });
+        Ok(())
+    }
+
+    // 恢复默认输入，保留金额单位.
+    pub fn reset(&mut self){
+        let unit = self.unit;
+        *self = Self::new();
+        self.set_unit(unit);
+    }
+}
+
+// 按期数合并还款频率相同的多个还款计划，合并后的利率为按剩余本金加权的年利率.
+// periods_per_year 为各计划共同的每年还款期数.
+pub fn combine_schedules<'a>(schedules: impl IntoIterator<Item = &'a [Payment]>, periods_per_year: u32) -> Vec<Payment>{
+    let mut combined: Vec<Payment> = Vec::new();
+    let mut total_money = 0.0;
+    for schedule in schedules {
+        if let Some(first) = schedule.first() {
+            total_money += first.balance + first.principal + first.prepayment;
+        }
+        for p in schedule {
+            let i = (p.period - 1) as usize;
+            if combined.len() <= i {
+                combined.resize_with(i + 1, Payment::default);
+            }
+            let c = &mut combined[i];
+            c.period = p.period;
+            c.payment += p.payment;
+            c.principal += p.principal;
+            c.interest += p.interest;
+            c.prepayment += p.prepayment;
+            c.balance += p.balance;
+            c.date = c.date.or(p.date);
+        }
+    }
+    let mut previous_balance = total_money;
+    for c in combined.iter_mut() {
+        c.rate = if previous_balance > 0.0 { c.interest / previous_balance * periods_per_year as f64 * 100.0 } else { 0.0 };
+        previous_balance = c.balance;
+    }
+    combined
+}
+
+// 还款方式对比：相同的贷款金额，分别以两种方案计算.
+#[derive(PartialEq)]
+pub struct Comparison{
+    pub loan_money: f64,
+    pub unit: currency::MoneyUnit,
+    pub loans: [Loan; 2],
+}
+
+impl Default for Comparison{
+    fn default() -> Self{
+        Self::new()
+    }
+}
+
+impl Comparison{
+    // 默认比较等额本息与等额本金.
+    pub fn new() -> Self{
+        let scenario = |loan_type: LoanType| Loan{
+            loan_type,
+            ..Loan::new()
+        };
+        Self{
+            loan_money: 0.0,
+            unit: currency::MoneyUnit::Wan,
+            loans: [scenario(LoanType::EqualInterest), scenario(LoanType::EqualPrincipal)],
+        }
+    }
+
+    // 使用 loan 的金额、单位、年限和利率作为两个方案的输入.
+    pub fn copy_inputs(&mut self, loan: &Loan){
+        self.loan_money = loan.loan_money;
+        self.unit = loan.unit;
+        for scenario in self.loans.iter_mut() {
+            scenario.loan_year = loan.loan_year;
+            scenario.loan_rate = loan.loan_rate;
+            scenario.set_unit(loan.unit);
+        }
+    }
+
+    // 切换金额单位，同一货币内换算已输入的贷款金额.
+    pub fn set_unit(&mut self, unit: currency::MoneyUnit){
+        self.loan_money *= self.unit.conversion_to(unit);
+        self.unit = unit;
+        for scenario in self.loans.iter_mut() {
+            scenario.set_unit(unit);
+        }
+    }
+
+    // 当前单位下的最高贷款金额.
+    pub fn max_money(&self) -> f64{
+        self.unit.from_base(MAX_MONEY * currency::WAN)
+    }
+
+    // 计算两个方案。返回出错方案的序号及其错误.
+    pub fn calc(&mut self) -> Result<(), Vec<(usize, LoanError)>>{
+        let mut errors = Vec::new();
+        for (i, scenario) in self.loans.iter_mut().enumerate() {
+            scenario.loan_money = self.loan_money;
+            scenario.unit = self.unit;
+            if let Err(e) = scenario.calc_monthly() {
+                errors.extend(e.into_iter().map(|e| (i, e)));
+            }
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64){
+        assert!((actual - expected).abs() < tolerance, "{} != {}", actual, expected);
+    }
+
+    fn dates(loan: &Loan, periods: u32) -> Vec<String>{
+        (1..=periods).map(|period| loan.payment_date(period).unwrap().to_string()).collect()
+    }
+
+    #[test]
+    fn payment_dates(){
+        // 起始日不是月末时，每期都在同一天还款，不会漂移到月末.
+        let mut loan = Loan{ start_date: Date::parse("2023-01-28"), ..Loan::new() };
+        assert_eq!(dates(&loan, 4), ["2023-02-28", "2023-03-28", "2023-04-28", "2023-05-28"]);
+        // 起始日为月末时，每期都在月末还款.
+        loan.start_date = Date::parse("2023-01-31");
+        assert_eq!(dates(&loan, 4), ["2023-02-28", "2023-03-31", "2023-04-30", "2023-05-31"]);
+        // 指定的首次还款日为锚定日.
+        loan.first_payment_date = Date::parse("2023-03-30");
+        assert_eq!(dates(&loan, 3), ["2023-03-30", "2023-04-30", "2023-05-30"]);
+        loan.first_payment_date = Date::parse("2023-02-28");
+        assert_eq!(dates(&loan, 3), ["2023-02-28", "2023-03-31", "2023-04-30"]);
+        // 按季度还款.
+        let loan = Loan{
+            start_date: Date::parse("2023-11-29"),
+            payment_frequency: rate::Frequency::Quarterly,
+            ..Loan::new()
+        };
+        assert_eq!(dates(&loan, 3), ["2024-02-29", "2024-05-29", "2024-08-29"]);
+        assert_eq!(Loan::new().payment_date(1), None);
+    }
+
+    #[test]
+    fn combination_requires_monthly_tranches(){
+        let mut combination = CombinationLoan::new();
+        for tranche in &mut combination.tranches {
+            tranche.loan.loan_money = 50.0;
+        }
+        assert_eq!(combination.calc(), Ok(()));
+        // 合并后的利率按每年 12 期换算为年利率.
+        let first = &combination.schedule[0];
+        assert!((first.rate - first.interest / 100.0 * 12.0 * 100.0).abs() < 1e-9);
+        combination.tranches[1].loan.loan_type = LoanType::BiWeekly;
+        assert_eq!(combination.calc(), Err(vec![(1, LoanError::InvalidFrequency)]));
+        assert!(combination.schedule.is_empty());
+    }
+
+    // 100 万元、30 年、年利率 4.9% 的等额本息贷款，月供 5307.27 元.
+    fn mortgage() -> Loan{
+        Loan{ loan_year: 30.0, loan_money: 100.0, loan_rate: 4.9, ..Loan::new() }
+    }
+
+    #[test]
+    fn first_payments(){
+        assert_close(first_payment(&mortgage()) * currency::WAN, 5307.27, 0.005);
+        let equal_principal = Loan{ loan_type: LoanType::EqualPrincipal, ..mortgage() };
+        assert_close(first_payment(&equal_principal) * currency::WAN, 6861.11, 0.005);
+    }
+
+    #[test]
+    fn solvers_round_trip(){
+        let payment = 5307.27 / currency::WAN;
+        assert_close(solve_rate(&Loan{ loan_rate: 0.0, ..mortgage() }, payment).unwrap(), 4.9, 1e-4);
+        assert_eq!(solve_periods(&Loan{ loan_year: 0.0, ..mortgage() }, payment), Some(360));
+        assert_close(solve_money(&Loan{ loan_money: 0.0, ..mortgage() }, payment).unwrap(), 100.0, 1e-4);
+        // 其他还款方式与单位.
+        for loan_type in [LoanType::EqualPrincipal, LoanType::GraduatedGeometric, LoanType::LumpSum] {
+            let loan = Loan{ loan_type, graduation: 2.0, loan_year: 10.0, ..mortgage() };
+            let payment = first_payment(&loan);
+            assert_close(solve_rate(&loan, payment).unwrap(), 4.9, 1e-6);
+            assert_eq!(solve_periods(&loan, payment), Some(120), "{}", loan_type.to_str());
+            assert_close(solve_money(&loan, payment).unwrap(), 100.0, 1e-6);
+        }
+        let mut dollars = mortgage();
+        dollars.set_unit(currency::MoneyUnit::Yuan);
+        dollars.set_unit(currency::MoneyUnit::Dollar);
+        assert_close(solve_money(&dollars, 5307.27).unwrap(), 1_000_000.0, 1.0);
+    }
+
+    #[test]
+    fn solvers_without_solution(){
+        // 月供低于首月利息时，期数再长也无法还清.
+        let payment = 4000.0 / currency::WAN;
+        assert_eq!(solve_periods(&mortgage(), payment), None);
+        assert_eq!(solve_rate(&mortgage(), -1.0), None);
+        // 先息后本的首期只付利息，与期数无关.
+        assert_eq!(solve_periods(&Loan{ loan_type: LoanType::InterestOnly, ..mortgage() }, payment), None);
+        // 超过贷款金额上限.
+        assert_eq!(solve_money(&mortgage(), 1e6), None);
+    }
+
+    // 剩余本金逐期减少并最终还清，各期本金之和等于贷款金额.
+    fn assert_amortizes(loan: &Loan){
+        let mut balance = loan.loan_money;
+        for p in &loan.schedule {
+            assert!(p.principal >= 0.0 && p.balance <= balance, "第 {} 期剩余本金 {} 增加", p.period, p.balance);
+            balance = p.balance;
+        }
+        assert_eq!(balance, 0.0);
+        assert_close(loan.schedule.iter().map(|p| p.principal).sum(), loan.loan_money, 1e-9);
+    }
+
+    #[test]
+    fn interest_only_and_lump_sum(){
+        let mut loan = Loan{ loan_type: LoanType::InterestOnly, ..mortgage() };
+        assert_eq!(loan.calc(), Ok(()));
+        assert_eq!(loan.schedule.len(), 360);
+        let result = loan.result.as_ref().unwrap();
+        // 每月利息 100 万元 * 4.9% / 12 = 4083.33 元，最后一期连同本金一起偿还.
+        assert_close(result.first_payment, 0.408333, 1e-6);
+        assert_close(result.last_payment, 100.408333, 1e-6);
+        assert_close(result.total_interest, 147.0, 1e-9);
+        assert!(loan.schedule[..359].iter().all(|p| p.principal == 0.0 && p.balance == 100.0));
+        assert_amortizes(&loan);
+
+        // 到期一次还本付息只有最后一期，利息按单利计算.
+        let mut loan = Loan{ loan_type: LoanType::LumpSum, ..mortgage() };
+        assert_eq!(loan.calc(), Ok(()));
+        assert_eq!(loan.schedule.len(), 1);
+        assert_eq!(loan.schedule[0].period, 360);
+        assert_close(loan.schedule[0].interest, 147.0, 1e-9);
+        assert_close(loan.result.as_ref().unwrap().total_paid, 247.0, 1e-9);
+        assert_amortizes(&loan);
+    }
+
+    #[test]
+    fn graduated_schedules(){
+        // 等比递增：每年的月供比上一年增加 2%.
+        let mut loan = Loan{ loan_type: LoanType::GraduatedGeometric, graduation: 2.0, ..mortgage() };
+        assert_eq!(loan.calc(), Ok(()));
+        let first = loan.schedule[0].payment;
+        assert_close(first * currency::WAN, 4214.11, 0.005);
+        assert_close(loan.schedule[11].payment, first, 1e-12);
+        assert_close(loan.schedule[12].payment, first * 1.02, 1e-12);
+        assert_close(loan.schedule[359].payment, first * 1.02f64.powi(29), 1e-9);
+        assert_close(loan.result.as_ref().unwrap().total_interest, 105.150242, 1e-6);
+        assert_amortizes(&loan);
+
+        // 等额递增：每年的月供比上一年增加 100 元.
+        let mut loan = Loan{ loan_type: LoanType::GraduatedArithmetic, graduation: 0.01, ..mortgage() };
+        assert_eq!(loan.calc(), Ok(()));
+        let first = loan.schedule[0].payment;
+        assert_close(first * currency::WAN, 4211.10, 0.005);
+        assert_close(loan.schedule[12].payment, first + 0.01, 1e-12);
+        assert_close(loan.schedule[359].payment, first + 0.29, 1e-9);
+        assert_close(loan.result.as_ref().unwrap().total_interest, 103.799466, 1e-6);
+        assert_amortizes(&loan);
+
+        // 递减还款的首期最高，剩余本金同样逐期减少.
+        let mut loan = Loan{ loan_type: LoanType::GraduatedGeometric, graduation: -3.0, ..mortgage() };
+        assert_eq!(loan.calc(), Ok(()));
+        assert_amortizes(&loan);
+    }
+
+    #[test]
+    fn graduated_rejects_negative_amortization(){
+        for (loan_type, graduation) in [
+            // 首期还款为负数.
+            (LoanType::GraduatedArithmetic, 0.5),
+            // 首期还款为正数，但低于首期利息.
+            (LoanType::GraduatedArithmetic, 0.05),
+            (LoanType::GraduatedGeometric, 3.0),
+            (LoanType::GraduatedGeometric, 10.0),
+            // 超过增幅上限.
+            (LoanType::GraduatedGeometric, -60.0),
+        ] {
+            let mut loan = Loan{ loan_type, graduation, ..mortgage() };
+            assert_eq!(loan.calc(), Err(vec![LoanError::InvalidGraduation]), "{} {}", loan_type.to_str(), graduation);
+            assert!(loan.schedule.is_empty());
+        }
+        // 其他输入无效时不检查首期还款.
+        let loan = Loan{ loan_type: LoanType::GraduatedArithmetic, graduation: 0.5, loan_money: 0.0, ..mortgage() };
+        assert_eq!(loan.validate(), Err(vec![LoanError::InvalidMoney]));
+    }
+
+    #[test]
+    fn bi_weekly_schedule(){
+        let mut loan = Loan{ loan_type: LoanType::BiWeekly, ..mortgage() };
+        assert_eq!(loan.calc(), Ok(()));
+        assert_eq!(loan.periods_per_year(), 26);
+        // 每两周还等额本息月供的一半，一年多还一个月的月供，约 25.3 年还清.
+        let result = loan.result.as_ref().unwrap();
+        assert_close(result.first_payment * currency::WAN, 5307.27 / 2.0, 0.005);
+        assert_eq!(loan.schedule.len(), 658);
+        assert!(result.last_payment < result.first_payment);
+        assert_close(result.total_interest, 74.373997, 1e-6);
+        assert_amortizes(&loan);
+    }
+
+    #[test]
+    fn solve_graduated_money(){
+        // 等额递增每年增加 100 元，首月还款 4211.10 元时可贷约 100 万元.
+        let mut loan = Loan{ loan_type: LoanType::GraduatedArithmetic, graduation: 0.01, loan_money: 0.0, ..mortgage() };
+        assert_eq!(loan.validate_solve(SolveFor::Money, 0.421110), Ok(()));
+        assert_eq!(loan.solve(SolveFor::Money, 0.421110), Ok(()));
+        assert_close(loan.loan_money, 100.0, 1e-3);
+        assert_amortizes(&loan);
+        // 其他未知量同样在求解后检查首期还款.
+        let mut loan = Loan{ loan_type: LoanType::GraduatedGeometric, graduation: 2.0, loan_rate: 0.0, ..mortgage() };
+        assert_eq!(loan.solve(SolveFor::Rate, 0.421411), Ok(()));
+        assert_close(loan.loan_rate, 4.9, 1e-4);
+        // 增幅超出范围时不求解.
+        let mut loan = Loan{ loan_type: LoanType::GraduatedGeometric, graduation: 60.0, ..mortgage() };
+        assert_eq!(loan.solve(SolveFor::Money, 0.5), Err(vec![LoanError::InvalidGraduation]));
+    }
+
+    #[test]
+    fn apr_with_fees(){
+        // 没有费用时 APR 等于名义年利率.
+        let mut loan = mortgage();
+        loan.calc().unwrap();
+        let result = loan.result.clone().unwrap();
+        assert_close(result.apr.unwrap(), 4.9, 1e-6);
+        assert_close(result.effective_annual_rate.unwrap(), 5.0116, 1e-4);
+        assert_close(result.total_fees, 0.0, 1e-12);
+
+        // 1 万元手续费和每月 10 元服务费使实际成本高于名义年利率.
+        let mut loan = Loan{ fees: Fees{ origination: 1.0, service: 0.001, insurance: 0.0 }, ..mortgage() };
+        loan.calc().unwrap();
+        let result = loan.result.clone().unwrap();
+        assert_close(result.apr.unwrap(), 5.0045, 1e-4);
+        assert_close(result.effective_annual_rate.unwrap(), 5.1209, 1e-4);
+        assert_close(result.total_fees, 1.36, 1e-9);
+        assert_close(loan.cash_flows()[0], 99.0, 1e-12);
+    }
+
+    #[test]
+    fn prepayment_strategies(){
+        // 第 12 个月还款后提前还款 20 万元.
+        let prepaid = |strategy| {
+            let mut loan = Loan{ prepayments: vec![Prepayment{ month: 12, amount: 20.0, strategy }], ..mortgage() };
+            loan.calc().unwrap();
+            loan
+        };
+        let assert_repaid = |loan: &Loan| {
+            assert_eq!(loan.schedule.last().map(|p| p.balance), Some(0.0));
+            assert_close(loan.schedule.iter().map(|p| p.principal + p.prepayment).sum(), loan.loan_money, 1e-9);
+        };
+        let shorten = prepaid(PrepayStrategy::ShortenTerm);
+        assert_eq!(shorten.schedule.len(), 240);
+        assert_close(shorten.schedule[12].payment, shorten.schedule[0].payment, 1e-9);
+        assert_close(shorten.result.as_ref().unwrap().interest_saved, 44.063038, 1e-5);
+        assert_repaid(&shorten);
+
+        let reduce = prepaid(PrepayStrategy::ReducePayment);
+        assert_eq!(reduce.schedule.len(), 360);
+        assert_close(reduce.schedule[11].prepayment, 20.0, 1e-12);
+        assert_close(reduce.schedule[12].payment * currency::WAN, 4229.63, 0.005);
+        assert_close(reduce.result.as_ref().unwrap().interest_saved, 17.501918, 1e-5);
+        assert_repaid(&reduce);
+    }
+
+    #[test]
+    fn rate_change_resets_payment(){
+        // 第 13 个月起利率调整为 4.2%，按剩余本金和剩余期数重新计算月供.
+        let mut loan = Loan{ rate_changes: vec![RateChange{ month: 13, rate: 4.2 }], ..mortgage() };
+        loan.calc().unwrap();
+        let schedule = &loan.schedule;
+        assert_eq!(schedule.len(), 360);
+        assert!(schedule[..12].iter().all(|p| p.rate == 4.9 && (p.payment * currency::WAN - 5307.27).abs() < 0.005));
+        assert_close(schedule[11].balance, 98.497841, 1e-6);
+        assert_eq!(schedule[12].rate, 4.2);
+        assert_close(schedule[12].payment * currency::WAN, 4900.05, 0.005);
+        assert_close(schedule[359].payment, schedule[12].payment, 1e-9);
+        assert_eq!(schedule[359].balance, 0.0);
+
+        // 加点同时作用于调整前后的利率.
+        loan.rate_spread_bp = 20.0;
+        loan.calc().unwrap();
+        assert_eq!(loan.schedule[12].rate, 4.4);
+        assert_close(loan.schedule[12].payment * currency::WAN, 5017.44, 0.005);
+    }
+
+    #[test]
+    fn set_unit(){
+        let mut loan = Loan{ fees: Fees{ origination: 1.0, ..Fees::default() }, ..mortgage() };
+        loan.set_unit(currency::MoneyUnit::Yuan);
+        assert_close(loan.loan_money, 1_000_000.0, 1e-6);
+        assert_close(loan.fees.origination, 10_000.0, 1e-9);
+        // 不同货币之间没有汇率，金额保持不变.
+        loan.set_unit(currency::MoneyUnit::Dollar);
+        assert_close(loan.loan_money, 1_000_000.0, 1e-6);
+        loan.set_unit(currency::MoneyUnit::Wan);
+        assert_close(loan.loan_money, 1_000_000.0, 1e-6);
+    }
+}
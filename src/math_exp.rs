@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use crate::token;
 use crate::token::Weight;
 
@@ -5,6 +7,35 @@ pub struct MathExp {
     tokens: Vec<token::Token>,
     buffer: String,
     output: String,
+    pub settings: Settings,
+}
+
+// 计算相关的设置.
+#[derive(Clone)]
+pub struct Settings {
+    // 计算时自动补全未闭合的括号.
+    pub auto_close_parens: bool,
+    // 使用 IEEE 754 语义：不检查定义域和溢出，允许结果为 NaN 或 inf.
+    pub ieee_semantics: bool,
+    // 三角函数参数的角度单位.
+    pub angle_mode: AngleMode,
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub enum AngleMode {
+    #[default]
+    Radian,
+    Degree,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            auto_close_parens: true,
+            ieee_semantics: false,
+            angle_mode: AngleMode::Radian,
+        }
+    }
 }
 
 impl Default for MathExp {
@@ -19,6 +50,7 @@ impl MathExp {
             tokens: Vec::new(),
             buffer: String::new(),
             output: String::new(),
+            settings: Settings::default(),
         }
     }
 
@@ -36,7 +68,8 @@ impl MathExp {
     // 操作数 后必须紧跟操作符号或闭括号
     // 操作符 后只能是开括号或操作数（数字）
 
-    fn push_to_token(&mut self, t: token::Token) {
+    // 返回标记是否被成功插入.
+    fn push_to_token(&mut self, t: token::Token) -> bool {
         fn push(tokens: &mut Vec<token::Token>, t: token::Token) {
             if let token::Token::Function(_) = t {
                 tokens.push(t);
@@ -56,7 +89,7 @@ impl MathExp {
                     }
                 }
             }
-            if count_paren < 0 { return; }
+            if count_paren < 0 { return false; }
         }
 
 
@@ -67,8 +100,9 @@ impl MathExp {
             // 如果它们不是操作的标记（左括号除外）。
             if !matches!(t,token::Token::Operation(_)) || matches!(t,token::Token::Operation(token::Op::ParenLeft)) {
                 push(&mut self.tokens, t);
+                return true;
             }
-            return;
+            return false;
         }
         let last_token = last_token.unwrap();
 
@@ -115,6 +149,7 @@ impl MathExp {
             s.push(']');
             s.push(',');
         }
+        allow_insert
     }
 
     // 从缓冲区中删除该值并将其放置在标记向量的末尾。
@@ -123,15 +158,8 @@ impl MathExp {
     fn pop_buffer(&mut self) -> bool {
         if self.buffer.is_empty() { return true; }
         if let Ok(val) = self.buffer.parse::<f64>() {
-            if val.is_sign_negative() {
-                self.tokens.push(token::Token::Operation(token::Op::ParenLeft));
-                self.tokens.push(token::Token::Operand(val));
-                self.tokens.push(token::Token::Operation(token::Op::ParenRight));
-                self.buffer.clear();
-            } else {
-                self.tokens.push(token::Token::Operand(val));
-                self.buffer.clear();
-            }
+            push_operand(&mut self.tokens, val);
+            self.buffer.clear();
             true
         } else { false }
     }
@@ -183,85 +211,270 @@ impl MathExp {
             self.buffer = s.to_string();
             true
         } else if let Ok(t) = token::Token::try_from(s) {
-            self.pop_buffer();
-            self.push_to_token(t);
-            true
+            self.pop_buffer() && self.push_to_token(t)
         } else { false }
     }
 
+    // 解析一整行文本形式的表达式，并按键盘输入的规则逐个添加到表达式中。
+    // 支持 sqrt 作为 √ 的别名，忽略空白字符。
+    pub fn input(&mut self, s: &str) -> Result<(), String> {
+        self.input_with_variables(s, &BTreeMap::new())
+    }
+
+    // 与 input 相同，但表达式中出现的变量名（仅由字母组成）会被替换为对应的值.
+    pub fn input_with_variables(&mut self, s: &str, variables: &BTreeMap<String, f64>) -> Result<(), String> {
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c.is_whitespace() { continue; }
+            // 负号跟在数值或右括号之后时为减号，紧跟在 ^ 之后时为指数的符号，例如 2^-2 = 2^(-2)；
+            // 其他位置为一元负号，作用于其后的整个乘方，例如 -2^2 = -(2^2)，以 (-1)* 表示.
+            if c == '-' && self.buffer.is_empty() {
+                match self.tokens.last() {
+                    Some(token::Token::Operand(_)) => {
+                        self.push_to_token(token::Token::Operation(token::Op::Sub));
+                        continue;
+                    }
+                    Some(token::Token::Operation(token::Op::ParenRight | token::Op::Exp)) => {}
+                    _ => {
+                        push_operand(&mut self.tokens, -1.0);
+                        self.tokens.push(token::Token::Operation(token::Op::Multi));
+                        continue;
+                    }
+                }
+            }
+            let piece = if c.is_alphabetic() {
+                let mut word = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if !next.is_alphabetic() { break; }
+                    word.push(next);
+                    chars.next();
+                }
+                if let Some(&val) = variables.get(&word) {
+                    if !(self.pop_buffer() && self.push_to_token(token::Token::Operand(val))) {
+                        return Err(format!("无法在此处添加: {}", word));
+                    }
+                    continue;
+                }
+                if word == "sqrt" { "√".to_string() } else { word }
+            } else { c.to_string() };
+            if !self.add(piece.as_str()) {
+                return Err(format!("无法识别或无法在此处添加: {}", piece));
+            }
+            // 函数标记已自带开括号，这里只消耗紧随其后输入的 "("，参数必须用括号括起来.
+            if token::Func::try_from(piece.as_str()).is_ok() {
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                if chars.next_if_eq(&'(').is_none() {
+                    return Err(format!("{} 的参数必须用括号括起来", piece));
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn calculate(&mut self) {
+        if let Err(e) = self.try_calculate() {
+            self.output = e;
+        }
+    }
+
+    // 计算表达式并返回结果，同时更新输出。计算成功后清空表达式.
+    pub fn try_calculate(&mut self) -> Result<f64, String> {
         self.pop_buffer();
-        let rpn = yard(&self.tokens);
-        match rpn {
-            Err(e) => { self.output = e }
-            Ok(tokens) => {
-                let mut stack: Vec<token::Token> = Vec::new();
-                for t in tokens {
-                    if stack.is_empty() {
-                        stack.push(t);
-                    } else {
-                        match t {
-                            token::Token::Function(f) => {
-                                if let Some(token::Token::Operand(val)) = stack.pop() {
-                                    stack.push(match f {
-                                        token::Func::Sin => {
-                                            token::Token::Operand(val.sin())
-                                        }
-                                        token::Func::Cos => {
-                                            token::Token::Operand(val.cos())
-                                        }
-                                        token::Func::Tg => {
-                                            token::Token::Operand(val.sin() / val.cos())
-                                        }
-                                        token::Func::Ctg => {
-                                            token::Token::Operand(val.cos() / val.sin())
-                                        }
-                                        token::Func::Sqrt => {
-                                            token::Token::Operand(val.sqrt())
-                                        }
-                                    });
-                                } else {
-                                    self.output = "计算错误".to_string()
-                                }
-                            }
-                            token::Token::Operation(op) => {
-                                if let Some(token::Token::Operand(second_val)) = stack.pop() {
-                                    if let Some(token::Token::Operand(first_val)) = stack.pop() {
-                                        if let Some(val) = match op {
-                                            token::Op::Add => {
-                                                Some(first_val + second_val)
-                                            }
-                                            token::Op::Sub => {
-                                                Some(first_val - second_val)
-                                            }
-                                            token::Op::Multi => {
-                                                Some(first_val * second_val)
-                                            }
-                                            token::Op::Div => {
-                                                Some(first_val / second_val)
-                                            }
-                                            token::Op::Exp => {
-                                                Some(first_val.powf(second_val))
-                                            }
-                                            _ => { None }
-                                        } { stack.push(token::Token::Operand(val)) }
-                                    } else { self.output = "计算错误".to_string() }
-                                } else { self.output = "计算错误".to_string() }
-                            }
-                            token::Token::Operand(_) => { stack.push(t); }
-                        }
-                    }
+        // 在副本上补全括号，计算失败时表达式保持原样.
+        let mut tokens = self.tokens.clone();
+        if self.settings.auto_close_parens {
+            for _ in 0..open_parens(&tokens) {
+                tokens.push(token::Token::Operation(token::Op::ParenRight));
+            }
+        }
+        let val = evaluate(&tokens, &self.settings)?;
+        self.buffer.clear();
+        self.tokens.clear();
+        self.output = token::Token::Operand(val).to_string();
+        Ok(val)
+    }
+
+    // 在不修改表达式的情况下计算预览结果。
+    // 允许不完整的输入：忽略末尾的运算符和函数，并自动补全未闭合的括号。
+    // 当表达式中没有任何运算或无法计算时返回 None。
+    pub fn preview(&self) -> Option<String> {
+        let mut tokens = self.tokens.clone();
+        if let Ok(val) = self.buffer.parse::<f64>() {
+            push_operand(&mut tokens, val);
+        }
+
+        // 去掉末尾尚未输入操作数的运算符和函数.
+        while let Some(last) = tokens.last() {
+            match last {
+                token::Token::Operand(_) | token::Token::Operation(token::Op::ParenRight) => { break; }
+                _ => { tokens.pop(); }
+            }
+        }
+
+        let has_operation = tokens.iter().any(|t| !matches!(
+            t,
+            token::Token::Operand(_) | token::Token::Operation(token::Op::ParenLeft | token::Op::ParenRight)
+        ));
+        if !has_operation { return None; }
+
+        for _ in 0..open_parens(&tokens) {
+            tokens.push(token::Token::Operation(token::Op::ParenRight));
+        }
+        evaluate(&tokens, &self.settings).ok().map(|val| token::Token::Operand(val).to_string())
+    }
+
+    // 尚未闭合、需要在显示时补全的括号数量.
+    pub fn ghost_parens(&self) -> usize {
+        open_parens(&self.tokens)
+    }
+
+    // 返回需要高亮的一对括号在显示文本中的字符位置（包括补全的括号）。
+    // 如果表达式以闭括号结尾，则匹配该括号；否则匹配最内层未闭合的开括号与第一个补全的括号。
+    pub fn matching_parens(&self) -> Option<(usize, usize)> {
+        let mut pos: usize = 0;
+        let mut open: Vec<usize> = Vec::new();
+        let mut last_pair = None;
+        for t in &self.tokens {
+            match t {
+                token::Token::Operation(token::Op::ParenLeft) => { open.push(pos) }
+                token::Token::Operation(token::Op::ParenRight) => {
+                    last_pair = open.pop().map(|left| (left, pos));
                 }
-                self.output = if let Some(t) = stack.pop() {
-                    self.buffer.clear();
-                    self.tokens.clear();
-                    t.to_string()
-                } else { "计算错误".to_string() };
+                _ => {}
             }
+            pos += t.to_string().chars().count();
+        }
+        let ends_with_paren = self.buffer.is_empty()
+            && matches!(self.tokens.last(), Some(token::Token::Operation(token::Op::ParenRight)));
+        if ends_with_paren {
+            last_pair
+        } else {
+            open.pop().map(|left| (left, pos + self.buffer.chars().count()))
         }
     }
 }
 
+// 将数值添加到标记向量的末尾，负数会被括号包围.
+fn push_operand(tokens: &mut Vec<token::Token>, val: f64) {
+    if val.is_sign_negative() {
+        tokens.push(token::Token::Operation(token::Op::ParenLeft));
+        tokens.push(token::Token::Operand(val));
+        tokens.push(token::Token::Operation(token::Op::ParenRight));
+    } else {
+        tokens.push(token::Token::Operand(val));
+    }
+}
+
+// 统计尚未闭合的开括号数量.
+fn open_parens(tokens: &[token::Token]) -> usize {
+    let mut count: usize = 0;
+    for t in tokens {
+        match t {
+            token::Token::Operation(token::Op::ParenLeft) => { count += 1 }
+            token::Token::Operation(token::Op::ParenRight) => { count = count.saturating_sub(1) }
+            _ => {}
+        }
+    }
+    count
+}
+
+// 将中缀表达式转换为逆波兰表达式并计算结果.
+fn evaluate(tokens: &Vec<token::Token>, settings: &Settings) -> Result<f64, String> {
+    let rpn = yard(tokens)?;
+    let mut stack: Vec<f64> = Vec::new();
+    for t in rpn {
+        match t {
+            token::Token::Function(f) => {
+                let val = stack.pop().ok_or_else(|| "计算错误".to_string())?;
+                stack.push(apply_func(&f, val, settings)?);
+            }
+            token::Token::Operation(op) => {
+                let second_val = stack.pop().ok_or_else(|| "计算错误".to_string())?;
+                let first_val = stack.pop().ok_or_else(|| "计算错误".to_string())?;
+                stack.push(apply_op(&op, first_val, second_val, settings.ieee_semantics)?);
+            }
+            token::Token::Operand(val) => { stack.push(val); }
+        }
+    }
+    match (stack.pop(), stack.is_empty()) {
+        (Some(val), true) => Ok(val),
+        _ => Err("计算错误".to_string()),
+    }
+}
+
+// 三角函数的极点判定阈值.
+const POLE_EPSILON: f64 = 1e-12;
+
+// 计算函数值。除非启用 IEEE 语义，否则会检查参数的定义域和结果是否溢出.
+fn apply_func(f: &token::Func, val: f64, settings: &Settings) -> Result<f64, String> {
+    let ieee = settings.ieee_semantics;
+    // 三角函数的参数统一转换为弧度.
+    let arg = match (f, settings.angle_mode) {
+        (token::Func::Sqrt, _) | (_, AngleMode::Radian) => { val }
+        (_, AngleMode::Degree) => { val.to_radians() }
+    };
+    if !ieee {
+        let out_of_domain = match f {
+            token::Func::Sqrt => { arg < 0.0 }
+            token::Func::Tg => { arg.cos().abs() < POLE_EPSILON }
+            token::Func::Ctg => { arg.sin().abs() < POLE_EPSILON }
+            token::Func::Sin | token::Func::Cos => { false }
+        };
+        if out_of_domain {
+            return Err(format!("{}({}) 超出定义域", f, val));
+        }
+    }
+    let result = match f {
+        token::Func::Sin => { arg.sin() }
+        token::Func::Cos => { arg.cos() }
+        token::Func::Tg => { arg.sin() / arg.cos() }
+        token::Func::Ctg => { arg.cos() / arg.sin() }
+        token::Func::Sqrt => { arg.sqrt() }
+    };
+    check_result(result, ieee)
+}
+
+// 计算二元运算。除非启用 IEEE 语义，否则会检查除数为零、定义域和结果是否溢出.
+fn apply_op(op: &token::Op, first_val: f64, second_val: f64, ieee: bool) -> Result<f64, String> {
+    if !ieee {
+        match op {
+            token::Op::Div if second_val == 0.0 => {
+                return Err("除数不能为零".to_string());
+            }
+            // 0 的负数次幂为极点，负数的非整数次幂没有实数结果.
+            token::Op::Exp if (first_val == 0.0 && second_val < 0.0) || (first_val < 0.0 && second_val.fract() != 0.0) => {
+                return Err(format!("{}^{} 超出定义域", parenthesize(first_val), parenthesize(second_val)));
+            }
+            _ => {}
+        }
+    }
+    let result = match op {
+        token::Op::Add => { first_val + second_val }
+        token::Op::Sub => { first_val - second_val }
+        token::Op::Multi => { first_val * second_val }
+        token::Op::Div => { first_val / second_val }
+        token::Op::Exp => { first_val.powf(second_val) }
+        _ => { return Err("计算错误".to_string()); }
+    };
+    check_result(result, ieee)
+}
+
+// 在错误信息中显示操作数，负数加上括号，例如 (-8)^0.5.
+fn parenthesize(val: f64) -> String {
+    if val.is_sign_negative() { format!("({})", val) } else { val.to_string() }
+}
+
+// 检查计算结果是否为有限数.
+fn check_result(result: f64, ieee: bool) -> Result<f64, String> {
+    if ieee || result.is_finite() {
+        Ok(result)
+    } else if result.is_nan() {
+        Err("计算结果无意义".to_string())
+    } else {
+        Err("数值溢出".to_string())
+    }
+}
+
 impl std::fmt::Display for MathExp {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut o = String::new();
@@ -309,6 +522,10 @@ fn yard(input: &Vec<token::Token>) -> Result<Vec<token::Token>, String> {
                     if let Some(last_token_in_stack) = stack.pop() {
                         match last_token_in_stack {
                             token::Token::Operation(token::Op::ParenLeft) => {
+                                // 括号属于函数时，函数紧随其参数输出.
+                                if let Some(token::Token::Function(_)) = stack.last() {
+                                    output.extend(stack.pop());
+                                }
                                 break;
                             }
                             _ => {
@@ -321,12 +538,16 @@ fn yard(input: &Vec<token::Token>) -> Result<Vec<token::Token>, String> {
                 }
             }
             token::Token::Operation(op1) => {
-                if let Some(token::Token::Operation(op2)) = stack.pop() {
-                    if op2.weight() >= op1.weight() {
-                        output.push(token::Token::Operation(op2))
-                    } else {
-                        stack.push(token::Token::Operation(op2))
+                // 弹出所有优先级不低于当前运算符的运算符，乘方为右结合.
+                while let Some(token::Token::Operation(op2)) = stack.last() {
+                    let higher = match op1 {
+                        token::Op::Exp => op2.weight() > op1.weight(),
+                        _ => op2.weight() >= op1.weight(),
+                    };
+                    if !higher {
+                        break;
                     }
+                    output.extend(stack.pop());
                 }
 
                 stack.push(token.clone())
@@ -342,4 +563,171 @@ fn yard(input: &Vec<token::Token>) -> Result<Vec<token::Token>, String> {
         }
     }
     Ok(output)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calc(input: &str) -> Result<f64, String> {
+        let mut exp = MathExp::new();
+        exp.input(input)?;
+        exp.try_calculate()
+    }
+
+    #[test]
+    fn function_arguments() {
+        assert_eq!(calc("sin(0)+1"), Ok(1.0));
+        assert_eq!(calc("√(4)*3"), Ok(6.0));
+        assert_eq!(calc("sqrt (9) - 1"), Ok(2.0));
+        assert_eq!(calc("√(2)"), Ok(2f64.sqrt()));
+        assert_eq!(calc("2*(√(16)+1)"), Ok(10.0));
+        assert_eq!(calc("√(9)-1"), Ok(2.0));
+        // 未闭合的括号在计算时自动补全.
+        assert_eq!(calc("√(4"), Ok(2.0));
+    }
+
+    #[test]
+    fn function_without_parens() {
+        assert!(calc("√4*3").is_err());
+        assert!(calc("sin 0").is_err());
+    }
+
+    #[test]
+    fn operator_precedence() {
+        // 同级运算符从左到右计算，此前只弹出栈顶的一个运算符.
+        assert_eq!(calc("1-2*3-4"), Ok(-9.0));
+        assert_eq!(calc("8/2/2"), Ok(2.0));
+        assert_eq!(calc("2*3^2"), Ok(18.0));
+        assert_eq!(calc("2^3^2"), Ok(512.0));
+        // 函数紧随其参数计算，此前遇到运算符时函数被丢弃.
+        let mut exp = MathExp::new();
+        for piece in ["√", "9", ")", "-", "1"] {
+            assert!(exp.add(piece));
+        }
+        assert_eq!(exp.try_calculate(), Ok(2.0));
+    }
+
+    #[test]
+    fn unary_minus() {
+        assert_eq!(calc("-2^2"), Ok(-4.0));
+        assert_eq!(calc("-2"), Ok(-2.0));
+        assert_eq!(calc("(-2)^2"), Ok(4.0));
+        assert_eq!(calc("2^-2"), Ok(0.25));
+        assert_eq!(calc("3*-2^2"), Ok(-12.0));
+        assert_eq!(calc("1-(-2)^2"), Ok(-3.0));
+        assert_eq!(calc("--2"), Ok(2.0));
+        assert_eq!(calc("-√(4)"), Ok(-2.0));
+        assert_eq!(calc("5-3"), Ok(2.0));
+    }
+
+    #[test]
+    fn variables() {
+        let variables = BTreeMap::from([("x".to_string(), 3.0)]);
+        let mut exp = MathExp::new();
+        exp.input_with_variables("x^2+cos(0)", &variables).unwrap();
+        assert_eq!(exp.try_calculate(), Ok(10.0));
+        // 变量之后的负号为减号.
+        exp.input_with_variables("x-2", &variables).unwrap();
+        assert_eq!(exp.try_calculate(), Ok(1.0));
+        exp.input_with_variables("-x^2", &variables).unwrap();
+        assert_eq!(exp.try_calculate(), Ok(-9.0));
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert!(calc("1+*2").is_err());
+        assert!(calc("abc").is_err());
+    }
+
+    #[test]
+    fn ghost_parens() {
+        let mut exp = MathExp::new();
+        exp.input("(1+(2").unwrap();
+        assert_eq!(exp.ghost_parens(), 2);
+        exp.input(")").unwrap();
+        assert_eq!(exp.ghost_parens(), 1);
+        exp.input(")*3").unwrap();
+        assert_eq!(exp.ghost_parens(), 0);
+        // 计算失败时补全的括号不会留在表达式中.
+        exp.clear();
+        exp.input("(1/0").unwrap();
+        assert!(exp.try_calculate().is_err());
+        assert_eq!(exp.ghost_parens(), 1);
+        assert_eq!(exp.tokens.iter().map(|t| t.to_string()).collect::<String>(), "(1/0");
+    }
+
+    #[test]
+    fn matching_parens() {
+        let matching = |input: &str| {
+            let mut exp = MathExp::new();
+            exp.input(input).unwrap();
+            exp.matching_parens()
+        };
+        // 未闭合时匹配最内层的开括号与第一个补全的括号.
+        assert_eq!(matching("(1+2"), Some((0, 4)));
+        assert_eq!(matching("(1+(2"), Some((3, 5)));
+        assert_eq!(matching("√(4"), Some((1, 3)));
+        // 以闭括号结尾时匹配该括号.
+        assert_eq!(matching("(1+2)"), Some((0, 4)));
+        assert_eq!(matching("2*(3+(4)"), Some((5, 7)));
+        assert_eq!(matching("(1+2)*3"), None);
+    }
+
+    #[test]
+    fn domain_errors() {
+        assert_eq!(calc("0^-1"), Err("0^(-1) 超出定义域".to_string()));
+        assert_eq!(calc("(-8)^(1/2)"), Err("(-8)^0.5 超出定义域".to_string()));
+        assert_eq!(calc("(-8)^2"), Ok(64.0));
+        assert_eq!(calc("√(-4)"), Err("√(-4) 超出定义域".to_string()));
+        assert_eq!(calc("1/0"), Err("除数不能为零".to_string()));
+        assert_eq!(calc("10^400"), Err("数值溢出".to_string()));
+        assert_eq!(calc("10^300*10^300"), Err("数值溢出".to_string()));
+    }
+
+    #[test]
+    fn ieee_semantics() {
+        let mut exp = MathExp::new();
+        exp.settings.ieee_semantics = true;
+        let mut calc = |input: &str| {
+            exp.input(input).unwrap();
+            exp.try_calculate().unwrap()
+        };
+        assert_eq!(calc("1/0"), f64::INFINITY);
+        assert_eq!(calc("0^-1"), f64::INFINITY);
+        assert_eq!(calc("10^400"), f64::INFINITY);
+        assert!(calc("(-8)^(1/2)").is_nan());
+        assert!(calc("√(-4)").is_nan());
+    }
+
+    #[test]
+    fn preview() {
+        let preview = |input: &str| {
+            let mut exp = MathExp::new();
+            exp.input(input).unwrap();
+            let text = exp.to_string();
+            let preview = exp.preview();
+            // 预览不修改表达式.
+            assert_eq!(exp.to_string(), text);
+            preview
+        };
+        // 忽略末尾的运算符和函数.
+        assert_eq!(preview("1+2*"), Some("3".to_string()));
+        assert_eq!(preview("2*3+√("), Some("6".to_string()));
+        assert_eq!(preview("1/("), None);
+        // 自动补全未闭合的括号.
+        assert_eq!(preview("(1+2"), Some("3".to_string()));
+        assert_eq!(preview("2*(3+(4"), Some("14".to_string()));
+        // 没有运算或无法计算时不显示预览.
+        assert_eq!(preview(""), None);
+        assert_eq!(preview("(5)"), None);
+        assert_eq!(preview("1/0"), None);
+        assert_eq!(preview("√(-4"), None);
+        // 尚未结束输入的数字也参与预览.
+        let mut exp = MathExp::new();
+        for piece in ["1", "+", "2", "5"] {
+            assert!(exp.add(piece));
+        }
+        assert_eq!(exp.preview(), Some("26".to_string()));
+    }
+}
@@ -0,0 +1,223 @@
+// 利率的换算：名义年利率、实际年利率与每期利率.
+
+// 还款或计息的频率.
+#[derive(Default,Clone,Copy,Debug,PartialEq)]
+pub enum Frequency{
+    Daily,
+    Weekly,
+    BiWeekly,
+    #[default]
+    Monthly,
+    Quarterly,
+    SemiAnnual,
+    Annual,
+}
+
+impl Frequency{
+    pub const ALL: [Frequency; 7] = [
+        Frequency::Daily,
+        Frequency::Weekly,
+        Frequency::BiWeekly,
+        Frequency::Monthly,
+        Frequency::Quarterly,
+        Frequency::SemiAnnual,
+        Frequency::Annual,
+    ];
+
+    pub fn to_str(&self) -> &str{
+        match self{
+            Frequency::Daily => "每日",
+            Frequency::Weekly => "每周",
+            Frequency::BiWeekly => "每两周",
+            Frequency::Monthly => "每月",
+            Frequency::Quarterly => "每季度",
+            Frequency::SemiAnnual => "每半年",
+            Frequency::Annual => "每年",
+        }
+    }
+
+    // 保存和导出时使用的标识.
+    pub fn as_str(&self) -> &'static str{
+        match self{
+            Frequency::Daily => "Daily",
+            Frequency::Weekly => "Weekly",
+            Frequency::BiWeekly => "BiWeekly",
+            Frequency::Monthly => "Monthly",
+            Frequency::Quarterly => "Quarterly",
+            Frequency::SemiAnnual => "SemiAnnual",
+            Frequency::Annual => "Annual",
+        }
+    }
+
+    // 每年的次数.
+    pub fn per_year(&self) -> u32{
+        match self{
+            Frequency::Daily => 365,
+            Frequency::Weekly => 52,
+            Frequency::BiWeekly => 26,
+            Frequency::Monthly => 12,
+            Frequency::Quarterly => 4,
+            Frequency::SemiAnnual => 2,
+            Frequency::Annual => 1,
+        }
+    }
+}
+
+impl std::str::FromStr for Frequency{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>{
+        Self::ALL.iter().copied().find(|v| v.as_str() == s).ok_or_else(|| format!("未知的频率: {}", s))
+    }
+}
+
+// 年利率的表示方式.
+#[derive(Default,Clone,Copy,Debug,PartialEq)]
+pub enum RateConvention{
+    // 名义年利率：每个计息周期的利率为年利率除以每年计息次数.
+    #[default]
+    Nominal,
+    // 实际年利率：一年内按复利计息后的实际收益率.
+    Effective,
+}
+
+impl RateConvention{
+    pub const ALL: [RateConvention; 2] = [RateConvention::Nominal, RateConvention::Effective];
+
+    pub fn to_str(&self) -> &str{
+        match self{
+            RateConvention::Nominal => "名义年利率",
+            RateConvention::Effective => "实际年利率",
+        }
+    }
+
+    // 保存和导出时使用的标识.
+    pub fn as_str(&self) -> &'static str{
+        match self{
+            RateConvention::Nominal => "Nominal",
+            RateConvention::Effective => "Effective",
+        }
+    }
+}
+
+impl std::str::FromStr for RateConvention{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>{
+        Self::ALL.iter().copied().find(|v| v.as_str() == s).ok_or_else(|| format!("未知的利率类型: {}", s))
+    }
+}
+
+// 以下利率均为小数形式，例如 5% 为 0.05.
+
+// 按 compounding 计息的名义年利率对应的实际年利率.
+pub fn effective_annual_rate(nominal: f64, compounding: Frequency) -> f64{
+    let m = compounding.per_year() as f64;
+    ((nominal / m).ln_1p() * m).exp_m1()
+}
+
+// 实际年利率对应的按 compounding 计息的名义年利率.
+pub fn nominal_annual_rate(effective: f64, compounding: Frequency) -> f64{
+    let m = compounding.per_year() as f64;
+    (effective.ln_1p() / m).exp_m1() * m
+}
+
+// 与年利率 annual 等价的每个还款周期的利率.
+pub fn periodic_rate(annual: f64, convention: RateConvention, compounding: Frequency, payment: Frequency) -> f64{
+    periodic_rate_per_year(annual, convention, compounding, payment.per_year())
+}
+
+// 与 periodic_rate 相同，但还款周期以每年的还款次数 payments_per_year 表示.
+pub fn periodic_rate_per_year(annual: f64, convention: RateConvention, compounding: Frequency, payments_per_year: u32) -> f64{
+    match convention{
+        RateConvention::Nominal => nominal_to_periodic(annual, compounding.per_year(), payments_per_year),
+        RateConvention::Effective => (annual.ln_1p() / payments_per_year as f64).exp_m1(),
+    }
+}
+
+// 每年计息 compounding_per_year 次的名义年利率，换算为每年还款 payments_per_year 次时的每期利率.
+pub fn nominal_to_periodic(nominal: f64, compounding_per_year: u32, payments_per_year: u32) -> f64{
+    let m = compounding_per_year as f64;
+    // 计息与还款频率相同时直接相除，避免浮点误差.
+    if compounding_per_year == payments_per_year {
+        return nominal / m;
+    }
+    ((nominal / m).ln_1p() * m / payments_per_year as f64).exp_m1()
+}
+
+// nominal_to_periodic 的逆运算.
+pub fn periodic_to_nominal(periodic: f64, compounding_per_year: u32, payments_per_year: u32) -> f64{
+    let m = compounding_per_year as f64;
+    if compounding_per_year == payments_per_year {
+        return periodic * m;
+    }
+    (periodic.ln_1p() * payments_per_year as f64 / m).exp_m1() * m
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64){
+        assert!((actual - expected).abs() < tolerance, "{} != {}", actual, expected);
+    }
+
+    // 名义年利率与实际年利率的对照表.
+    const EFFECTIVE_RATES: [(f64, Frequency, f64); 8] = [
+        (0.12, Frequency::Annual, 0.12),
+        (0.12, Frequency::SemiAnnual, 0.1236),
+        (0.12, Frequency::Quarterly, 0.125509),
+        (0.12, Frequency::Monthly, 0.126825),
+        (0.12, Frequency::Daily, 0.127475),
+        (0.10, Frequency::Quarterly, 0.103813),
+        (0.06, Frequency::Monthly, 0.061678),
+        (0.08, Frequency::Weekly, 0.083220),
+    ];
+
+    #[test]
+    fn nominal_to_effective(){
+        for (nominal, compounding, effective) in EFFECTIVE_RATES {
+            assert_close(effective_annual_rate(nominal, compounding), effective, 5e-7);
+        }
+    }
+
+    #[test]
+    fn effective_to_nominal(){
+        for (nominal, compounding, effective) in EFFECTIVE_RATES {
+            assert_close(nominal_annual_rate(effective, compounding), nominal, 1e-5);
+            // 换算应可逆.
+            let rate = effective_annual_rate(nominal, compounding);
+            assert_close(nominal_annual_rate(rate, compounding), nominal, 1e-12);
+        }
+    }
+
+    #[test]
+    fn periodic_rates(){
+        // 名义年利率按月计息、按月还款.
+        assert_close(periodic_rate(0.06, RateConvention::Nominal, Frequency::Monthly, Frequency::Monthly), 0.005, 1e-15);
+        // 加拿大房贷：按半年计息的 6% 名义年利率，按月还款.
+        assert_close(periodic_rate(0.06, RateConvention::Nominal, Frequency::SemiAnnual, Frequency::Monthly), 0.0049386220, 1e-10);
+        // 实际年利率 12.6825% 等价于按月计息的 12% 名义年利率.
+        assert_close(periodic_rate(0.126825030, RateConvention::Effective, Frequency::Monthly, Frequency::Monthly), 0.01, 1e-10);
+        // 按季度计息、按年还款时的每期利率即实际年利率.
+        assert_close(periodic_rate(0.10, RateConvention::Nominal, Frequency::Quarterly, Frequency::Annual), 0.10381289, 1e-8);
+    }
+
+    #[test]
+    fn loan_payment_tables(){
+        use crate::{currency, loan};
+        // 按月计息：10 万元贷款 30 年，年利率 6%，月供 599.55 元.
+        let monthly = loan::Loan{ loan_year: 30.0, loan_money: 10.0, loan_rate: 6.0, ..loan::Loan::new() };
+        assert_close(loan::first_payment(&monthly) * currency::WAN, 599.55, 0.005);
+        // 按半年计息：10 万元贷款 25 年，年利率 6%，月供 639.81 元.
+        let semi_annual = loan::Loan{ loan_year: 25.0, compounding: Frequency::SemiAnnual, ..monthly.clone() };
+        assert_close(loan::first_payment(&semi_annual) * currency::WAN, 639.81, 0.005);
+        // 实际年利率：与按月计息的名义年利率换算后月供相同.
+        let effective = loan::Loan{
+            loan_rate: effective_annual_rate(0.06, Frequency::Monthly) * 100.0,
+            rate_convention: RateConvention::Effective,
+            ..monthly.clone()
+        };
+        assert_close(loan::first_payment(&effective), loan::first_payment(&monthly), 1e-12);
+    }
+}
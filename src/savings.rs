@@ -0,0 +1,257 @@
+// 储蓄与复利计算，金额单位为元.
+
+use crate::rate;
+
+// 每期存入的时点.
+#[derive(Default,Clone,Copy,Debug,PartialEq)]
+pub enum ContributionTiming{
+    // 期末存入，当期不计息.
+    #[default]
+    End,
+    // 期初存入，当期计息.
+    Start,
+}
+
+impl ContributionTiming{
+    pub fn to_str(&self) -> &str{
+        match self{
+            ContributionTiming::End => "期末",
+            ContributionTiming::Start => "期初",
+        }
+    }
+}
+
+// 储蓄的计算结果.
+#[derive(Default,Clone,Debug,PartialEq)]
+pub struct SavingsResult{
+    // 到期总额.
+    pub final_value: f64,
+    // 本金与每期存入的总额.
+    pub total_contributions: f64,
+    // 利息总额.
+    pub interest_earned: f64,
+}
+
+// 每年的增长情况.
+#[derive(Default,Clone,Debug,PartialEq)]
+pub struct SavingsYear{
+    // 第几年，从 1 开始.
+    pub year: u32,
+    // 当年存入的金额，第一年包括本金.
+    pub contributions: f64,
+    // 当年获得的利息.
+    pub interest: f64,
+    // 年末余额.
+    pub balance: f64,
+}
+
+// 储蓄输入中出错的字段.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum SavingsField{
+    Principal,
+    Contribution,
+    Rate,
+    Year,
+}
+
+// 储蓄输入的校验错误.
+#[derive(Clone,Debug,PartialEq)]
+pub enum SavingsError{
+    // 本金为负数.
+    InvalidPrincipal,
+    // 每期存入为负数，或本金与每期存入都为 0.
+    InvalidContribution,
+    // 年利率为负数或超过上限.
+    InvalidRate,
+    // 存期不足一期或超过上限.
+    InvalidYear,
+}
+
+impl SavingsError{
+    pub fn field(&self) -> SavingsField{
+        match self{
+            SavingsError::InvalidPrincipal => SavingsField::Principal,
+            SavingsError::InvalidContribution => SavingsField::Contribution,
+            SavingsError::InvalidRate => SavingsField::Rate,
+            SavingsError::InvalidYear => SavingsField::Year,
+        }
+    }
+}
+
+impl std::fmt::Display for SavingsError{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result{
+        match self{
+            SavingsError::InvalidPrincipal => write!(f, "本金不能为负数"),
+            SavingsError::InvalidContribution => write!(f, "每期存入不能为负数，且本金和每期存入不能都为 0"),
+            SavingsError::InvalidRate => write!(f, "年利率必须在 0% 到 {}% 之间", MAX_RATE),
+            SavingsError::InvalidYear => write!(f, "存期必须在一期到 {} 年之间", MAX_YEAR),
+        }
+    }
+}
+
+// 存期的上限(年).
+pub const MAX_YEAR: f64 = 100.0;
+// 年利率的上限(%).
+pub const MAX_RATE: f64 = 100.0;
+
+#[derive(Clone,PartialEq)]
+pub struct Savings{
+    // 期初一次性存入的本金.
+    pub principal: f64,
+    // 每期存入的金额.
+    pub contribution: f64,
+    // 存入频率，也是计算利息的周期.
+    pub contribution_frequency: rate::Frequency,
+    // 名义年利率(%).
+    pub rate: f64,
+    // 计息频率.
+    pub compounding: rate::Frequency,
+    // 存期(年).
+    pub years: f64,
+    pub timing: ContributionTiming,
+    pub result: Option<SavingsResult>,
+    pub growth: Vec<SavingsYear>,
+}
+
+impl Default for Savings{
+    fn default() -> Self{
+        Self::new()
+    }
+}
+
+impl Savings{
+    pub fn new() -> Self{
+        Self{
+            principal: 0.0,
+            contribution: 0.0,
+            contribution_frequency: rate::Frequency::Monthly,
+            rate: 0.0,
+            compounding: rate::Frequency::Annual,
+            years: 0.0,
+            timing: ContributionTiming::End,
+            result: None,
+            growth: Vec::new(),
+        }
+    }
+
+    // 存期内的总期数.
+    pub fn periods(&self) -> u32{
+        (self.years * self.contribution_frequency.per_year() as f64).round() as u32
+    }
+
+    // 校验输入，返回所有出错字段的错误.
+    pub fn validate(&self) -> Result<(), Vec<SavingsError>>{
+        let mut errors = Vec::new();
+        if self.principal < 0.0 {
+            errors.push(SavingsError::InvalidPrincipal);
+        }
+        if self.contribution < 0.0 || (self.principal == 0.0 && self.contribution == 0.0) {
+            errors.push(SavingsError::InvalidContribution);
+        }
+        if !(0.0..=MAX_RATE).contains(&self.rate) {
+            errors.push(SavingsError::InvalidRate);
+        }
+        if self.periods() == 0 || self.years > MAX_YEAR {
+            errors.push(SavingsError::InvalidYear);
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    // 计算到期总额和每年的增长情况。输入无效时清空结果并返回错误.
+    pub fn calc(&mut self) -> Result<(), Vec<SavingsError>>{
+        self.result = None;
+        self.growth.clear();
+        self.validate()?;
+
+        let per_year = self.contribution_frequency.per_year();
+        let rate = rate::periodic_rate(self.rate / 100.0, rate::RateConvention::Nominal, self.compounding, self.contribution_frequency);
+        let mut balance = self.principal;
+        let mut year = SavingsYear{ year: 1, contributions: self.principal, ..SavingsYear::default() };
+        for period in 1..=self.periods() {
+            if self.timing == ContributionTiming::Start {
+                balance += self.contribution;
+            }
+            let interest = balance * rate;
+            balance += interest;
+            if self.timing == ContributionTiming::End {
+                balance += self.contribution;
+            }
+            year.contributions += self.contribution;
+            year.interest += interest;
+            // 每满一年或存期结束时记录一行.
+            if period % per_year == 0 || period == self.periods() {
+                year.balance = balance;
+                let next = year.year + 1;
+                self.growth.push(std::mem::replace(&mut year, SavingsYear{ year: next, ..SavingsYear::default() }));
+            }
+        }
+
+        let total_contributions = self.principal + self.contribution * self.periods() as f64;
+        self.result = Some(SavingsResult{
+            final_value: balance,
+            total_contributions,
+            interest_earned: balance - total_contributions,
+        });
+        Ok(())
+    }
+
+    pub fn reset(&mut self){
+        *self = Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64){
+        assert!((actual - expected).abs() < tolerance, "{} != {}", actual, expected);
+    }
+
+    // 每月存入 1000 元，年利率 5%，按月计息，存 10 年.
+    fn monthly_deposit() -> Savings{
+        Savings{
+            contribution: 1000.0,
+            rate: 5.0,
+            compounding: rate::Frequency::Monthly,
+            years: 10.0,
+            ..Savings::new()
+        }
+    }
+
+    #[test]
+    fn monthly_deposit_future_value(){
+        let mut savings = monthly_deposit();
+        savings.calc().unwrap();
+        let result = savings.result.clone().unwrap();
+        assert_close(result.final_value, 155_282.28, 0.005);
+        assert_close(result.total_contributions, 120_000.0, 1e-9);
+        assert_close(result.interest_earned, 35_282.28, 0.005);
+        assert_eq!(savings.growth.len(), 10);
+        assert_close(savings.growth[0].balance, 12_278.86, 0.005);
+        assert_close(savings.growth.iter().map(|y| y.interest).sum(), result.interest_earned, 1e-6);
+
+        // 期初存入时每笔多计一期利息.
+        savings.timing = ContributionTiming::Start;
+        savings.calc().unwrap();
+        assert_close(savings.result.unwrap().final_value, 155_929.29, 0.005);
+    }
+
+    #[test]
+    fn principal_with_annual_compounding(){
+        let mut savings = Savings{ principal: 10_000.0, compounding: rate::Frequency::Annual, ..monthly_deposit() };
+        savings.calc().unwrap();
+        assert_close(savings.result.unwrap().final_value, 170_652.11, 0.005);
+    }
+
+    #[test]
+    fn invalid_input(){
+        let mut savings = Savings{ contribution: 0.0, rate: -1.0, years: 0.0, ..Savings::new() };
+        assert_eq!(savings.calc(), Err(vec![
+            SavingsError::InvalidContribution,
+            SavingsError::InvalidRate,
+            SavingsError::InvalidYear,
+        ]));
+        assert!(savings.result.is_none());
+    }
+}
@@ -0,0 +1,163 @@
+// 命名保存的贷款方案，以 JSON 格式持久化：本地版本保存在 eframe 的存储文件中，网页版本保存在 localStorage 中.
+// 方案只保存贷款的输入，加载后需要重新计算.
+
+use crate::export::{loan_from_json, loan_to_json};
+use crate::loan::Loan;
+
+// 在 eframe 存储中使用的键.
+pub const STORAGE_KEY: &str = "loan_scenarios";
+// 保存的方案无法读取时，原始内容备份到该键下，避免被覆盖.
+pub const BACKUP_KEY: &str = "loan_scenarios_backup";
+
+#[derive(Clone,PartialEq)]
+pub struct Scenario{
+    pub name: String,
+    pub loan: Loan,
+}
+
+// 方案操作的错误.
+#[derive(Clone,Debug,PartialEq)]
+pub enum ScenarioError{
+    // 名称为空.
+    EmptyName,
+    // 保存的内容无法解析，例如包含无法识别的还款方式.
+    InvalidData(String),
+}
+
+impl std::fmt::Display for ScenarioError{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result{
+        match self{
+            ScenarioError::EmptyName => write!(f, "方案名称不能为空"),
+            ScenarioError::InvalidData(reason) => write!(f, "无法读取保存的方案: {}", reason),
+        }
+    }
+}
+
+#[derive(Default,Clone,PartialEq)]
+pub struct Scenarios{
+    pub scenarios: Vec<Scenario>,
+}
+
+impl Scenarios{
+    // 以 name 保存 loan 的输入，已有同名方案时覆盖.
+    pub fn save(&mut self, name: &str, loan: &Loan) -> Result<(), ScenarioError>{
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(ScenarioError::EmptyName);
+        }
+        let scenario = Scenario{ name: name.to_string(), loan: inputs(loan) };
+        match self.scenarios.iter_mut().find(|s| s.name == name){
+            Some(existing) => *existing = scenario,
+            None => self.scenarios.push(scenario),
+        }
+        Ok(())
+    }
+
+    // 第 index 个方案的贷款输入.
+    pub fn load(&self, index: usize) -> Option<Loan>{
+        self.scenarios.get(index).map(|s| s.loan.clone())
+    }
+
+    // 复制第 index 个方案，副本名称后加序号以避免重名.
+    pub fn duplicate(&mut self, index: usize){
+        let Some(scenario) = self.scenarios.get(index) else {
+            return;
+        };
+        let name = (1..)
+            .map(|i| format!("{} 副本{}", scenario.name, if i == 1 { String::new() } else { i.to_string() }))
+            .find(|name| self.scenarios.iter().all(|s| &s.name != name))
+            .unwrap_or_default();
+        let copy = Scenario{ name, loan: scenario.loan.clone() };
+        self.scenarios.insert(index + 1, copy);
+    }
+
+    pub fn delete(&mut self, index: usize){
+        if index < self.scenarios.len() {
+            self.scenarios.remove(index);
+        }
+    }
+
+    pub fn to_json(&self) -> String{
+        let scenarios: Vec<serde_json::Value> = self.scenarios.iter().map(|s| serde_json::json!({
+            "name": s.name,
+            "loan": loan_to_json(&s.loan),
+        })).collect();
+        serde_json::to_string(&scenarios).unwrap_or_default()
+    }
+
+    // 解析 to_json 的结果，任何一个方案无法解析时返回错误.
+    pub fn from_json(text: &str) -> Result<Self, ScenarioError>{
+        let invalid = ScenarioError::InvalidData;
+        let value: serde_json::Value = serde_json::from_str(text).map_err(|e| invalid(e.to_string()))?;
+        let items = value.as_array().ok_or_else(|| invalid("不是方案列表".to_string()))?;
+        let scenarios = items.iter().map(|item| {
+            let name = item.get("name").and_then(|v| v.as_str()).ok_or_else(|| invalid("缺少方案名称".to_string()))?;
+            let loan = item.get("loan").ok_or_else(|| invalid(format!("方案 {} 缺少贷款参数", name)))?;
+            Ok(Scenario{
+                name: name.to_string(),
+                loan: loan_from_json(loan).map_err(|e| invalid(format!("方案 {}: {}", name, e)))?,
+            })
+        }).collect::<Result<_, _>>()?;
+        Ok(Self{ scenarios })
+    }
+}
+
+// 只保留贷款的输入.
+fn inputs(loan: &Loan) -> Loan{
+    Loan{ result: None, schedule: Vec::new(), ..loan.clone() }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::currency::MoneyUnit;
+    use crate::date::{Date, DayCount};
+    use crate::loan::{self, LoanType, PrepayStrategy};
+    use crate::rate::{Frequency, RateConvention};
+
+    #[test]
+    fn json_round_trip(){
+        let loan = Loan{
+            loan_type: LoanType::GraduatedArithmetic,
+            unit: MoneyUnit::Dollar,
+            loan_year: 15.0,
+            loan_money: 300_000.0,
+            loan_rate: 6.5,
+            graduation: 1200.0,
+            payment_frequency: Frequency::Quarterly,
+            rate_convention: RateConvention::Effective,
+            compounding: Frequency::SemiAnnual,
+            start_date: Date::parse("2023-01-28"),
+            first_payment_date: Date::parse("2023-03-01"),
+            day_count: DayCount::Thirty360,
+            fees: loan::Fees{ origination: 2000.0, service: 10.0, insurance: 5.0 },
+            rate_changes: vec![loan::RateChange{ month: 12, rate: 5.5 }],
+            prepayments: vec![loan::Prepayment{ month: 24, amount: 10_000.0, strategy: PrepayStrategy::ReducePayment }],
+            ..Loan::new()
+        };
+        let mut scenarios = Scenarios::default();
+        assert_eq!(scenarios.save(" ", &loan), Err(ScenarioError::EmptyName));
+        scenarios.save("方案", &loan).unwrap();
+        scenarios.duplicate(0);
+        let json = scenarios.to_json();
+        // 枚举按 as_str 的标识保存.
+        assert!(json.contains("\"GraduatedArithmetic\"") && json.contains("\"ReducePayment\""), "{}", json);
+        let loaded = Scenarios::from_json(&json).unwrap();
+        assert!(loaded == scenarios);
+        assert_eq!(loaded.scenarios[1].name, "方案 副本");
+        assert!(loaded.load(0) == Some(loan));
+    }
+
+    #[test]
+    fn invalid_json(){
+        let json = Scenarios::default().to_json();
+        assert!(Scenarios::from_json(&json) == Ok(Scenarios::default()));
+        let unknown = r#"[{"name":"方案","loan":{"loan_type":"Balloon"}}]"#;
+        assert_eq!(
+            Scenarios::from_json(unknown).err(),
+            Some(ScenarioError::InvalidData("方案 方案: 未知的还款方式: Balloon".to_string())),
+        );
+        assert!(Scenarios::from_json(r#"[{"loan":{}}]"#).is_err());
+        assert!(Scenarios::from_json("{").is_err());
+    }
+}
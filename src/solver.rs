@@ -0,0 +1,118 @@
+// 数值求根，供贷款反求等计算共用.
+
+// 根的精度.
+pub const TOLERANCE: f64 = 1e-10;
+// 迭代次数上限.
+const MAX_ITERATIONS: usize = 200;
+
+// 在 [lo, hi] 内求 f 的根，f(lo) 与 f(hi) 必须异号或其一为零，否则返回 None.
+// 使用 Illinois 改进的试位法：收敛速度接近割线法，且根始终保持在区间内.
+pub fn find_root(f: impl Fn(f64) -> f64, lo: f64, hi: f64) -> Option<f64> {
+    let (mut a, mut b) = (lo, hi);
+    let (mut fa, mut fb) = (f(a), f(b));
+    if !fa.is_finite() || !fb.is_finite() {
+        return None;
+    }
+    if fa == 0.0 {
+        return Some(a);
+    }
+    if fb == 0.0 {
+        return Some(b);
+    }
+    if fa.signum() == fb.signum() {
+        return None;
+    }
+    // 上一次保留的端点：连续两次保留同一端点时将其函数值减半，避免收敛停滞.
+    let mut side = 0;
+    for _ in 0..MAX_ITERATIONS {
+        let c = (a * fb - b * fa) / (fb - fa);
+        let fc = f(c);
+        if !fc.is_finite() {
+            return None;
+        }
+        if fc == 0.0 || (b - a).abs() < TOLERANCE * (1.0 + c.abs()) {
+            return Some(c);
+        }
+        if fc.signum() == fb.signum() {
+            b = c;
+            fb = fc;
+            if side == -1 {
+                fa /= 2.0;
+            }
+            side = -1;
+        } else {
+            a = c;
+            fa = fc;
+            if side == 1 {
+                fb /= 2.0;
+            }
+            side = 1;
+        }
+    }
+    Some((a * fb - b * fa) / (fb - fa))
+}
+
+// 将 [lo, hi] 等分为 steps 段，返回所有 f 变号的子区间，可用于查找多个根.
+// 恰好落在分点上的根只计入以其为右端点的子区间.
+pub fn brackets(f: impl Fn(f64) -> f64, lo: f64, hi: f64, steps: usize) -> Vec<(f64, f64)> {
+    let step = (hi - lo) / steps as f64;
+    let mut result = Vec::new();
+    let mut x0 = lo;
+    let mut f0 = f(x0);
+    for i in 1..=steps {
+        let x1 = if i == steps { hi } else { lo + step * i as f64 };
+        let f1 = f(x1);
+        let changes_sign = f1 == 0.0 || if f0 == 0.0 { i == 1 } else { f0.signum() != f1.signum() };
+        if f0.is_finite() && f1.is_finite() && changes_sign {
+            result.push((x0, x1));
+        }
+        x0 = x1;
+        f0 = f1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!((actual - expected).abs() < tolerance, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn roots() {
+        assert_close(find_root(|x| x * x - 2.0, 0.0, 2.0).unwrap(), 2f64.sqrt(), 1e-10);
+        assert_close(find_root(|x| x.cos() - x, 0.0, 1.0).unwrap(), 0.739_085_133_215, 1e-10);
+        // 区间端点可以颠倒.
+        assert_close(find_root(|x| x.powi(3) - x - 1.0, 2.0, 1.0).unwrap(), 1.324_717_957_245, 1e-10);
+        // 平坦的函数也能在迭代次数内收敛.
+        assert_close(find_root(|x| (x - 0.3).powi(5), 0.0, 1.0).unwrap(), 0.3, 1e-2);
+    }
+
+    #[test]
+    fn endpoints_and_invalid_brackets() {
+        assert_eq!(find_root(|x| x - 1.0, 1.0, 3.0), Some(1.0));
+        assert_eq!(find_root(|x| x - 3.0, 1.0, 3.0), Some(3.0));
+        // 两端同号.
+        assert_eq!(find_root(|x| x * x + 1.0, -1.0, 1.0), None);
+        // 端点的函数值无效.
+        assert_eq!(find_root(|x| 1.0 / x, 0.0, 1.0), None);
+    }
+
+    #[test]
+    fn sign_changes() {
+        // sin 在 (0.5, 10] 内的根为 π、2π、3π.
+        let found = brackets(f64::sin, 0.5, 10.0, 100);
+        assert_eq!(found.len(), 3);
+        for ((lo, hi), k) in found.into_iter().zip(1..) {
+            let root = std::f64::consts::PI * k as f64;
+            assert!(lo < root && root <= hi, "{} not in ({}, {})", root, lo, hi);
+            assert_close(find_root(f64::sin, lo, hi).unwrap(), root, 1e-9);
+        }
+        // 落在分点上的根只计一次.
+        assert_eq!(brackets(|x| x - 1.0, 0.0, 2.0, 4), vec![(0.5, 1.0)]);
+        assert_eq!(brackets(|x| x, 0.0, 1.0, 4), vec![(0.0, 0.25)]);
+        assert!(brackets(|x| x * x + 1.0, -1.0, 1.0, 10).is_empty());
+    }
+}
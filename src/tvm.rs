@@ -0,0 +1,272 @@
+// 货币时间价值(TVM)：N、I/Y、PV、PMT、FV 五个变量中已知四个求另一个.
+//
+// 现金流的符号约定与财务计算器相同：收入为正，支出为负，满足
+// PV * (1 + i)^n + PMT * (1 + i * b) * ((1 + i)^n - 1) / i + FV = 0，
+// 其中 i 为每期利率，期初付款时 b = 1，期末付款时 b = 0.
+
+use crate::{rate, solver};
+
+// 每期付款的时点.
+#[derive(Default,Clone,Copy,Debug,PartialEq)]
+pub enum PaymentTiming{
+    #[default]
+    End,
+    Begin,
+}
+
+impl PaymentTiming{
+    pub fn to_str(&self) -> &str{
+        match self{
+            PaymentTiming::End => "END(期末)",
+            PaymentTiming::Begin => "BEGIN(期初)",
+        }
+    }
+
+    fn factor(&self, rate: f64) -> f64{
+        match self{
+            PaymentTiming::End => 1.0,
+            PaymentTiming::Begin => 1.0 + rate,
+        }
+    }
+}
+
+// 年金终值系数 ((1 + i)^n - 1) / i，i 为 0 时取极限 n.
+fn annuity_factor(rate: f64, n: f64) -> f64{
+    if rate == 0.0 {
+        n
+    } else {
+        (n * rate.ln_1p()).exp_m1() / rate
+    }
+}
+
+// 求终值.
+pub fn future_value(n: f64, rate: f64, pv: f64, pmt: f64, timing: PaymentTiming) -> f64{
+    -(pv * (1.0 + rate).powf(n) + pmt * timing.factor(rate) * annuity_factor(rate, n))
+}
+
+// 求现值.
+pub fn present_value(n: f64, rate: f64, pmt: f64, fv: f64, timing: PaymentTiming) -> f64{
+    -(fv + pmt * timing.factor(rate) * annuity_factor(rate, n)) / (1.0 + rate).powf(n)
+}
+
+// 求每期付款.
+pub fn payment(n: f64, rate: f64, pv: f64, fv: f64, timing: PaymentTiming) -> f64{
+    -(pv * (1.0 + rate).powf(n) + fv) / (timing.factor(rate) * annuity_factor(rate, n))
+}
+
+// 求期数，无解时返回 None.
+pub fn periods(rate: f64, pv: f64, pmt: f64, fv: f64, timing: PaymentTiming) -> Option<f64>{
+    let n = if rate == 0.0 {
+        -(pv + fv) / pmt
+    } else {
+        let annuity = pmt * timing.factor(rate) / rate;
+        ((annuity - fv) / (annuity + pv)).ln() / rate.ln_1p()
+    };
+    (n.is_finite() && n > 0.0).then_some(n)
+}
+
+// 每期利率的搜索范围.
+const RATE_MIN: f64 = -0.99;
+const RATE_MAX: f64 = 1.0;
+
+// 求每期利率，无解时返回 None。有多个解时返回最接近 0 的一个.
+pub fn rate(n: f64, pv: f64, pmt: f64, fv: f64, timing: PaymentTiming) -> Option<f64>{
+    let residual = |rate: f64| {
+        pv * (1.0 + rate).powf(n) + pmt * timing.factor(rate) * annuity_factor(rate, n) + fv
+    };
+    solver::brackets(residual, RATE_MIN, RATE_MAX, 200)
+        .into_iter()
+        .filter_map(|(lo, hi)| solver::find_root(residual, lo, hi))
+        .min_by(|a, b| a.abs().total_cmp(&b.abs()))
+}
+
+// 可以求解的变量.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum TvmVariable{
+    N,
+    Rate,
+    PresentValue,
+    Payment,
+    FutureValue,
+}
+
+impl TvmVariable{
+    pub fn to_str(&self) -> &str{
+        match self{
+            TvmVariable::N => "N",
+            TvmVariable::Rate => "I/Y",
+            TvmVariable::PresentValue => "PV",
+            TvmVariable::Payment => "PMT",
+            TvmVariable::FutureValue => "FV",
+        }
+    }
+}
+
+#[derive(Clone,Debug,PartialEq)]
+pub enum TvmError{
+    // P/Y 或 C/Y 为 0.
+    InvalidFrequency,
+    // 求解其他变量时期数不大于 0.
+    InvalidPeriods,
+    // 在允许的范围内找不到解.
+    NoSolution(TvmVariable),
+}
+
+impl std::fmt::Display for TvmError{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result{
+        match self{
+            TvmError::InvalidFrequency => write!(f, "P/Y 和 C/Y 必须大于 0"),
+            TvmError::InvalidPeriods => write!(f, "N 必须大于 0"),
+            TvmError::NoSolution(variable) => write!(f, "{} 无解，请检查现金流的正负号", variable.to_str()),
+        }
+    }
+}
+
+#[derive(Clone,Debug,PartialEq)]
+pub struct Tvm{
+    // 期数.
+    pub n: f64,
+    // 名义年利率(%).
+    pub rate: f64,
+    pub pv: f64,
+    pub pmt: f64,
+    pub fv: f64,
+    // 每年付款次数(P/Y).
+    pub payments_per_year: u32,
+    // 每年计息次数(C/Y).
+    pub compounding_per_year: u32,
+    pub timing: PaymentTiming,
+}
+
+impl Default for Tvm{
+    fn default() -> Self{
+        Self::new()
+    }
+}
+
+impl Tvm{
+    pub fn new() -> Self{
+        Self{
+            n: 0.0,
+            rate: 0.0,
+            pv: 0.0,
+            pmt: 0.0,
+            fv: 0.0,
+            payments_per_year: 12,
+            compounding_per_year: 12,
+            timing: PaymentTiming::End,
+        }
+    }
+
+    // 每期利率.
+    pub fn periodic_rate(&self) -> f64{
+        rate::nominal_to_periodic(self.rate / 100.0, self.compounding_per_year, self.payments_per_year)
+    }
+
+    // 根据其他四个变量求解 unknown，并写回对应的字段.
+    pub fn solve(&mut self, unknown: TvmVariable) -> Result<f64, TvmError>{
+        if self.payments_per_year == 0 || self.compounding_per_year == 0 {
+            return Err(TvmError::InvalidFrequency);
+        }
+        if unknown != TvmVariable::N && self.n <= 0.0 {
+            return Err(TvmError::InvalidPeriods);
+        }
+        let i = self.periodic_rate();
+        let value = match unknown{
+            TvmVariable::N => periods(i, self.pv, self.pmt, self.fv, self.timing),
+            TvmVariable::Rate => rate(self.n, self.pv, self.pmt, self.fv, self.timing)
+                .map(|i| rate::periodic_to_nominal(i, self.compounding_per_year, self.payments_per_year) * 100.0),
+            TvmVariable::PresentValue => Some(present_value(self.n, i, self.pmt, self.fv, self.timing)),
+            TvmVariable::Payment => Some(payment(self.n, i, self.pv, self.fv, self.timing)),
+            TvmVariable::FutureValue => Some(future_value(self.n, i, self.pv, self.pmt, self.timing)),
+        };
+        let value = value.filter(|v| v.is_finite()).ok_or(TvmError::NoSolution(unknown))?;
+        *self.field_mut(unknown) = value;
+        Ok(value)
+    }
+
+    pub fn field_mut(&mut self, variable: TvmVariable) -> &mut f64{
+        match variable{
+            TvmVariable::N => &mut self.n,
+            TvmVariable::Rate => &mut self.rate,
+            TvmVariable::PresentValue => &mut self.pv,
+            TvmVariable::Payment => &mut self.pmt,
+            TvmVariable::FutureValue => &mut self.fv,
+        }
+    }
+
+    pub fn reset(&mut self){
+        *self = Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64){
+        assert!((actual - expected).abs() < tolerance, "{} != {}", actual, expected);
+    }
+
+    // 100 万元、30 年、年利率 4.9% 的房贷.
+    fn mortgage() -> Tvm{
+        Tvm{ n: 360.0, rate: 4.9, pv: 1_000_000.0, ..Tvm::new() }
+    }
+
+    #[test]
+    fn mortgage_payment(){
+        let mut tvm = mortgage();
+        assert_close(tvm.solve(TvmVariable::Payment).unwrap(), -5307.27, 0.005);
+        assert_close(tvm.pmt, -5307.27, 0.005);
+        // 利率为 0 时每期平均偿还.
+        assert_close(payment(12.0, 0.0, 1200.0, 0.0, PaymentTiming::End), -100.0, 1e-9);
+    }
+
+    #[test]
+    fn begin_and_end_timing(){
+        let i = 0.049 / 12.0;
+        let end = payment(360.0, i, 1_000_000.0, 0.0, PaymentTiming::End);
+        let begin = payment(360.0, i, 1_000_000.0, 0.0, PaymentTiming::Begin);
+        assert_close(begin, -5285.68, 0.005);
+        assert_close(begin * (1.0 + i), end, 1e-9);
+        // 每期存入 100，年利率 5%，10 年后的终值.
+        assert_close(future_value(10.0, 0.05, 0.0, -100.0, PaymentTiming::End), 1257.79, 0.005);
+        assert_close(future_value(10.0, 0.05, 0.0, -100.0, PaymentTiming::Begin), 1320.68, 0.005);
+    }
+
+    #[test]
+    fn future_value_of_deposit(){
+        let mut tvm = Tvm{ n: 10.0, rate: 5.0, pv: -1000.0, payments_per_year: 1, compounding_per_year: 1, ..Tvm::new() };
+        assert_close(tvm.solve(TvmVariable::FutureValue).unwrap(), 1628.89, 0.005);
+    }
+
+    #[test]
+    fn solve_round_trips(){
+        let mut tvm = mortgage();
+        tvm.solve(TvmVariable::Payment).unwrap();
+        for timing in [PaymentTiming::End, PaymentTiming::Begin]{
+            let mut solved = Tvm{ timing, ..tvm.clone() };
+            solved.solve(TvmVariable::Payment).unwrap();
+            for (variable, expected) in [
+                (TvmVariable::Rate, 4.9),
+                (TvmVariable::N, 360.0),
+                (TvmVariable::PresentValue, 1_000_000.0),
+            ]{
+                let mut tvm = solved.clone();
+                *tvm.field_mut(variable) = 0.0;
+                assert_close(tvm.solve(variable).unwrap(), expected, 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn errors(){
+        let mut tvm = Tvm{ payments_per_year: 0, ..mortgage() };
+        assert_eq!(tvm.solve(TvmVariable::Payment), Err(TvmError::InvalidFrequency));
+        let mut tvm = Tvm{ n: 0.0, ..mortgage() };
+        assert_eq!(tvm.solve(TvmVariable::Payment), Err(TvmError::InvalidPeriods));
+        // 现金流同号时利率无解.
+        let mut tvm = Tvm{ pmt: 5000.0, ..mortgage() };
+        assert_eq!(tvm.solve(TvmVariable::Rate), Err(TvmError::NoSolution(TvmVariable::Rate)));
+    }
+}